@@ -0,0 +1,108 @@
+//! Compares testing every player bullet against every enemy with narrowing the tests down through
+//! the broad phase first. Run with `cargo bench --bench broad_phase`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use impacted::CollisionShape;
+use rand::{Rng, SeedableRng};
+use shoe_shmup::{BroadPhase, CollisionLayer};
+
+const BULLETS: usize = 5000;
+const ENEMIES: usize = 300;
+const ITERATIONS: u32 = 20;
+const BULLET_RADIUS: f32 = 4.0;
+const ENEMY_SIZE: f32 = 30.0;
+
+struct Body {
+    entity: Entity,
+    position: Vec2,
+    shape: CollisionShape,
+}
+
+fn bodies(rng: &mut impl Rng, count: usize, first: u32, shape: &CollisionShape) -> Vec<Body> {
+    (0..count)
+        .map(|i| {
+            let position = Vec2::new(rng.gen_range(-400.0..400.0), rng.gen_range(-3000.0..3000.0));
+            Body {
+                entity: Entity::from_raw(first + i as u32),
+                position,
+                shape: shape
+                    .clone()
+                    .with_transform(GlobalTransform::from_translation(position.extend(0.0))),
+            }
+        })
+        .collect()
+}
+
+fn time(name: &str, mut run: impl FnMut() -> usize) {
+    let mut total = Duration::ZERO;
+    let mut hits = 0;
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        hits = black_box(run());
+        total += start.elapsed();
+    }
+    println!("{name:>12}: {:?} per tick, {hits} hits", total / ITERATIONS);
+}
+
+fn main() {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+    let bullets = bodies(
+        &mut rng,
+        BULLETS,
+        0,
+        &CollisionShape::new_circle(BULLET_RADIUS),
+    );
+    let enemies = bodies(
+        &mut rng,
+        ENEMIES,
+        BULLETS as u32,
+        &CollisionShape::new_rectangle(ENEMY_SIZE, ENEMY_SIZE),
+    );
+    println!("{BULLETS} bullets against {ENEMIES} enemies");
+
+    time("naive", || {
+        let mut hits = 0;
+        for bullet in &bullets {
+            hits += enemies
+                .iter()
+                .filter(|enemy| bullet.shape.is_collided_with(&enemy.shape))
+                .count();
+        }
+        hits
+    });
+
+    let mut broad_phase = BroadPhase::default();
+    let shapes: std::collections::HashMap<Entity, &CollisionShape> = bullets
+        .iter()
+        .chain(&enemies)
+        .map(|body| (body.entity, &body.shape))
+        .collect();
+    time("broad phase", || {
+        // rebuilding the grid is part of every tick
+        broad_phase.clear();
+        for bullet in &bullets {
+            broad_phase.insert(
+                bullet.entity,
+                CollisionLayer::PlayerBullet,
+                bullet.position,
+                Vec2::splat(BULLET_RADIUS),
+            );
+        }
+        for enemy in &enemies {
+            broad_phase.insert(
+                enemy.entity,
+                CollisionLayer::Enemy,
+                enemy.position,
+                Vec2::splat(ENEMY_SIZE / 2.0),
+            );
+        }
+        broad_phase
+            .candidate_pairs(CollisionLayer::PlayerBullet, CollisionLayer::Enemy)
+            .into_iter()
+            .filter(|(bullet, enemy)| shapes[bullet].is_collided_with(shapes[enemy]))
+            .count()
+    });
+}
@@ -1,23 +1,103 @@
-use crate::{physics::Velocity, GameState};
-use bevy::{prelude::*, window::PrimaryWindow};
+use crate::{
+    input_map::{AimMode, InputAction, InputDevices, InputMap},
+    physics::Velocity,
+    player::Player,
+    playfield::cursor_to_world,
+    replay::reads_input_devices,
+    GameState,
+};
+use bevy::{
+    input::gamepad::{GamepadConnection, GamepadConnectionEvent},
+    prelude::*,
+    window::PrimaryWindow,
+};
+
+// how far the right stick has to be pushed to aim, so letting go keeps the last direction
+const AIM_STICK_THRESHOLD: f32 = 0.5;
+// step between the directions of `AimMode::KeyboardEightWay`
+const EIGHT_WAY_STEP: f32 = std::f32::consts::FRAC_PI_4;
 
 pub struct ActionsPlugin;
 
 // This plugin listens for keyboard input and converts the input into Actions
 // Actions can then be used as a resource in other systems to act on the player input.
+// Can be added on top of `HeadlessGamePlugin` to drive a headless game with input events.
 impl Plugin for ActionsPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<Actions>()
-            .configure_set(ActionsSet.in_set(OnUpdate(GameState::Playing)))
+        if !app.is_plugin_added::<HeadlessActionsPlugin>() {
+            app.add_plugin(HeadlessActionsPlugin);
+        }
+        app.init_resource::<KeyboardAim>()
+            .init_resource::<StickAim>()
+            .add_system(update_active_gamepad)
+            .add_system(reset_aim.in_schedule(OnEnter(GameState::LoadLevel)))
             .add_systems(
-                (set_movement_actions, set_point_actions, set_shoot_action).in_set(ActionsSet),
+                (
+                    set_movement_actions,
+                    set_point_actions.before(set_stick_point_action),
+                    set_stick_point_action,
+                    set_keyboard_point_action,
+                    set_shoot_action,
+                )
+                    .in_set(ActionsSet)
+                    .distributive_run_if(reads_input_devices),
             );
     }
 }
 
+// Sets up the Actions resource without reading any input devices,
+// so whoever owns the app can write Actions directly.
+pub struct HeadlessActionsPlugin;
+
+impl Plugin for HeadlessActionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Actions>()
+            .configure_set(ActionsSet.in_set(OnUpdate(GameState::Playing)));
+    }
+}
+
 #[derive(SystemSet, Debug, PartialEq, Eq, Hash, Clone)]
 pub struct ActionsSet;
 
+/// The gamepad the player uses, the first one connected.
+/// When it is disconnected another connected gamepad takes over.
+#[derive(Resource, Default)]
+pub struct ActiveGamepad(pub Option<Gamepad>);
+
+fn update_active_gamepad(
+    mut connections: EventReader<GamepadConnectionEvent>,
+    gamepads: Res<Gamepads>,
+    mut active: ResMut<ActiveGamepad>,
+) {
+    for event in connections.iter() {
+        match event.connection {
+            GamepadConnection::Connected(_) if active.0.is_none() => {
+                active.0 = Some(event.gamepad);
+            }
+            GamepadConnection::Disconnected if active.0 == Some(event.gamepad) => {
+                active.0 = gamepads
+                    .iter()
+                    .filter(|gamepad| *gamepad != event.gamepad)
+                    .min_by_key(|gamepad| gamepad.id);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn stick(
+    axes: &Axis<GamepadAxis>,
+    gamepad: Gamepad,
+    x: GamepadAxisType,
+    y: GamepadAxisType,
+) -> Vec2 {
+    let value = |axis_type| {
+        axes.get(GamepadAxis::new(gamepad, axis_type))
+            .unwrap_or(0.0)
+    };
+    Vec2::new(value(x), value(y))
+}
+
 #[derive(Resource, Default)]
 pub struct Actions {
     pub player_movement: Option<Vec2>,
@@ -28,36 +108,159 @@ pub struct Actions {
 
 fn set_movement_actions(
     mut actions: ResMut<Actions>,
-    keyboard_input: Res<Input<KeyCode>>,
-    mouse_button: Res<Input<MouseButton>>,
+    input_map: Res<InputMap>,
+    devices: InputDevices,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
 ) {
-    actions.player_stop =
-        keyboard_input.pressed(KeyCode::F) || mouse_button.pressed(MouseButton::Right);
+    actions.player_stop = input_map.pressed(InputAction::Stop, &devices);
+
+    // steering picks the branch to take at rail junctions
+    let mut steer = Vec2::ZERO;
+    for (action, direction) in [
+        (InputAction::SteerUp, Vec2::Y),
+        (InputAction::SteerDown, Vec2::NEG_Y),
+        (InputAction::SteerRight, Vec2::X),
+        (InputAction::SteerLeft, Vec2::NEG_X),
+    ] {
+        if input_map.pressed(action, &devices) {
+            steer += direction;
+        }
+    }
+    if let Some(gamepad) = devices.gamepad.0 {
+        steer += stick(
+            &gamepad_axes,
+            gamepad,
+            GamepadAxisType::LeftStickX,
+            GamepadAxisType::LeftStickY,
+        );
+    }
+    actions.player_movement = (steer != Vec2::ZERO).then_some(steer);
 }
 
+// Aims at the cursor, recomputed every frame so the aim stays put on screen while the camera scrolls.
+// Once another device aims somewhere else the cursor is ignored until it moves again.
 fn set_point_actions(
     mut actions: ResMut<Actions>,
-    mut cursor_pos: EventReader<CursorMoved>,
-    camera: Query<&Transform, (With<Camera>, With<Velocity>)>,
+    mut cursor_moved: EventReader<CursorMoved>,
+    camera: Query<(&Camera, &Transform), With<Velocity>>,
     window: Query<&Window, With<PrimaryWindow>>,
+    input_map: Res<InputMap>,
+    mut stick_aim: ResMut<StickAim>,
+    mut last_point: Local<Option<Vec2>>,
+) {
+    let moved = cursor_moved.iter().count() > 0;
+    if input_map.aim_mode != AimMode::Pointer {
+        return;
+    }
+    if moved {
+        stick_aim.0 = None;
+    }
+    if !moved && actions.player_point != *last_point {
+        return;
+    }
+    let Ok(window) = window.get_single() else {
+        return;
+    };
+    let (camera, transform) = camera.single();
+    // the global transform is only updated after the camera scrolled this frame
+    let Some(point) = cursor_to_world(window, camera, &GlobalTransform::from(*transform)) else {
+        return;
+    };
+    actions.player_point = Some(point);
+    *last_point = Some(point);
+}
+
+/// Direction the right stick last aimed in, until the cursor aims somewhere else
+#[derive(Resource, Default)]
+struct StickAim(Option<Vec2>);
+
+// aims from the player in the direction the right stick is pushed
+fn set_stick_point_action(
+    mut actions: ResMut<Actions>,
+    mut stick_aim: ResMut<StickAim>,
+    gamepad: Res<ActiveGamepad>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    player: Query<&Transform, With<Player>>,
+    input_map: Res<InputMap>,
 ) {
-    let window = window.single();
-    for position in cursor_pos.iter() {
-        let transform = camera.single();
-        // convert cursor_pos into world coordinates
-        let size = Vec2::new(window.width() as f32, window.height() as f32);
-        actions.player_point =
-            Some(position.position - size / 2.0 + transform.translation.truncate());
+    if input_map.aim_mode != AimMode::Pointer {
+        return;
+    }
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    if let Some(gamepad) = gamepad.0 {
+        let aim = stick(
+            &gamepad_axes,
+            gamepad,
+            GamepadAxisType::RightStickX,
+            GamepadAxisType::RightStickY,
+        );
+        if aim.length() >= AIM_STICK_THRESHOLD {
+            stick_aim.0 = Some(aim.normalize());
+        }
     }
+    // follows the player along the rail, like the keyboard aim
+    if let Some(direction) = stick_aim.0 {
+        actions.player_point = Some(player.translation.truncate() + direction);
+    }
+}
+
+/// Where the player aims in the keyboard `AimMode`s
+#[derive(Resource, Default)]
+struct KeyboardAim {
+    // radians counterclockwise from straight up, like the player's rotation
+    angle: f32,
+}
+
+fn reset_aim(mut keyboard_aim: ResMut<KeyboardAim>, mut stick_aim: ResMut<StickAim>) {
+    *keyboard_aim = KeyboardAim::default();
+    *stick_aim = StickAim::default();
 }
 
-fn set_shoot_action(
+fn set_keyboard_point_action(
     mut actions: ResMut<Actions>,
-    keyboard_input: Res<Input<KeyCode>>,
-    mouse_button: Res<Input<MouseButton>>,
+    mut aim: ResMut<KeyboardAim>,
+    input_map: Res<InputMap>,
+    devices: InputDevices,
+    player: Query<&Transform, With<Player>>,
+    time: Res<Time>,
 ) {
+    match input_map.aim_mode {
+        AimMode::Pointer => return,
+        AimMode::KeyboardRotate => {
+            let mut turn = 0.0;
+            if input_map.pressed(InputAction::AimLeft, &devices) {
+                turn += 1.0;
+            }
+            if input_map.pressed(InputAction::AimRight, &devices) {
+                turn -= 1.0;
+            }
+            aim.angle += turn * input_map.aim_rotation_speed.to_radians() * time.delta_seconds();
+        }
+        AimMode::KeyboardEightWay => {
+            // snapping first lets a turned aim from the other mode join the 8 directions
+            let mut step = (aim.angle / EIGHT_WAY_STEP).round();
+            if input_map.just_pressed(InputAction::AimLeft, &devices) {
+                step += 1.0;
+            }
+            if input_map.just_pressed(InputAction::AimRight, &devices) {
+                step -= 1.0;
+            }
+            aim.angle = step * EIGHT_WAY_STEP;
+        }
+    }
+    aim.angle %= std::f32::consts::TAU;
+
+    if let Ok(player) = player.get_single() {
+        let direction = Vec2::from_angle(aim.angle).rotate(Vec2::Y);
+        actions.player_point = Some(player.translation.truncate() + direction);
+    }
+}
+
+fn set_shoot_action(mut actions: ResMut<Actions>, input_map: Res<InputMap>, devices: InputDevices) {
     actions.player_shoot = if actions.player_stop {
-        keyboard_input.just_pressed(KeyCode::Space) || mouse_button.just_pressed(MouseButton::Left)
+        input_map.just_pressed(InputAction::Shoot, &devices)
     } else {
         false
     };
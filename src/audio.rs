@@ -12,6 +12,15 @@ impl Plugin for InternalAudioPlugin {
     }
 }
 
+// Provides the Audio resource without an audio device. Sounds are queued but never played.
+pub struct HeadlessAudioPlugin;
+
+impl Plugin for HeadlessAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Audio>();
+    }
+}
+
 fn start_audio(audio: Res<Audio>) {
     audio.set_volume(0.5);
 }
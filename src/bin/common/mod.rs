@@ -0,0 +1,51 @@
+use std::fs;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy::render::settings::WgpuSettings;
+use bevy::render::RenderPlugin;
+use bevy::window::ExitCondition;
+use bevy::winit::WinitPlugin;
+use shoe_shmup::HeadlessGamePlugin;
+
+const LEVELS_DIR: &str = "assets/levels";
+
+/// The level files given on the command line, or every `.scn.ron` file in `assets/levels`
+pub fn level_files() -> std::io::Result<Vec<PathBuf>> {
+    let args: Vec<PathBuf> = std::env::args().skip(1).map(PathBuf::from).collect();
+    if !args.is_empty() {
+        return Ok(args);
+    }
+    let mut files = Vec::new();
+    for entry in fs::read_dir(LEVELS_DIR)? {
+        let path = entry?.path();
+        if path.to_string_lossy().ends_with(".scn.ron") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// The game without a window, for its type registry.
+/// Plugins register their types when they are added, so the app never has to run.
+pub fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins(
+        DefaultPlugins
+            .set(RenderPlugin {
+                wgpu_settings: WgpuSettings {
+                    backends: None,
+                    ..default()
+                },
+            })
+            .set(WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                close_when_requested: false,
+            })
+            .disable::<WinitPlugin>(),
+    )
+    .add_plugin(HeadlessGamePlugin);
+    app
+}
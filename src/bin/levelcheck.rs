@@ -0,0 +1,34 @@
+//! Checks level files for mistakes the game would silently ignore, exiting with an error if any
+//! are found. Run from the repository root with the level files to check,
+//! or without arguments to check every `.scn.ron` file in `assets/levels`.
+
+mod common;
+
+use std::error::Error;
+use std::fs;
+use std::process::ExitCode;
+
+use bevy::prelude::*;
+use shoe_shmup::{check_level, EnemyArchetypes};
+
+const ARCHETYPES_FILE: &str = "assets/enemies/archetypes.enemies.ron";
+
+fn main() -> Result<ExitCode, Box<dyn Error>> {
+    let app = common::headless_app();
+    let type_registry = app.world.resource::<AppTypeRegistry>();
+    let archetypes: EnemyArchetypes = ron::from_str(&fs::read_to_string(ARCHETYPES_FILE)?)?;
+
+    let mut failed = false;
+    for file in common::level_files()? {
+        let problems = check_level(type_registry, &archetypes, &fs::read_to_string(&file)?);
+        for problem in &problems {
+            println!("{}: {problem}", file.display());
+        }
+        failed |= !problems.is_empty();
+    }
+    Ok(if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
@@ -0,0 +1,27 @@
+//! Rewrites level files in place so they only hold authored components.
+//! Run from the repository root with the level files to migrate,
+//! or without arguments to migrate every `.scn.ron` file in `assets/levels`.
+
+mod common;
+
+use std::error::Error;
+use std::fs;
+
+use bevy::prelude::*;
+use shoe_shmup::migrate_level;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let app = common::headless_app();
+    let type_registry = app.world.resource::<AppTypeRegistry>();
+
+    for file in common::level_files()? {
+        let contents = fs::read_to_string(&file)?;
+        let migrated = migrate_level(type_registry, &contents)
+            .map_err(|error| format!("failed to migrate {}: {error}", file.display()))?;
+        if migrated != contents {
+            fs::write(&file, migrated)?;
+            println!("migrated {}", file.display());
+        }
+    }
+    Ok(())
+}
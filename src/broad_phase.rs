@@ -0,0 +1,156 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+
+use crate::physics::{CollisionBounds, CollisionLayer, Swept, UpdateCollisionShapes, Velocity};
+
+// a bit bigger than the largest enemy, so most shapes only touch a few cells
+const DEFAULT_CELL_SIZE: f32 = 64.0;
+
+struct Entry {
+    entity: Entity,
+    min: Vec2,
+    max: Vec2,
+}
+
+impl Entry {
+    fn overlaps(&self, min: Vec2, max: Vec2) -> bool {
+        self.min.cmple(max).all() && min.cmple(self.max).all()
+    }
+}
+
+/// Uniform grid of the collision shapes' bounding boxes, rebuilt every tick in
+/// `UpdateCollisionShapes`. Narrows down which shapes are worth an exact collision test.
+#[derive(Resource)]
+pub struct BroadPhase {
+    cell_size: f32,
+    entries: Vec<Entry>,
+    /// indices into `entries` by layer
+    layers: HashMap<CollisionLayer, Vec<usize>>,
+    /// indices into `entries` by layer and cell
+    cells: HashMap<(CollisionLayer, IVec2), Vec<usize>>,
+}
+
+impl Default for BroadPhase {
+    fn default() -> Self {
+        BroadPhase::new(DEFAULT_CELL_SIZE)
+    }
+}
+
+impl BroadPhase {
+    pub fn new(cell_size: f32) -> Self {
+        BroadPhase {
+            cell_size,
+            entries: Vec::new(),
+            layers: HashMap::new(),
+            cells: HashMap::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.layers.clear();
+        self.cells.clear();
+    }
+
+    pub fn insert(
+        &mut self,
+        entity: Entity,
+        layer: CollisionLayer,
+        center: Vec2,
+        half_extents: Vec2,
+    ) {
+        let index = self.entries.len();
+        let (min, max) = (center - half_extents, center + half_extents);
+        self.entries.push(Entry { entity, min, max });
+        self.layers.entry(layer).or_default().push(index);
+        for cell in self.cells_between(min, max) {
+            self.cells.entry((layer, cell)).or_default().push(index);
+        }
+    }
+
+    fn cells_between(&self, min: Vec2, max: Vec2) -> impl Iterator<Item = IVec2> {
+        let min = (min / self.cell_size).floor().as_ivec2();
+        let max = (max / self.cell_size).floor().as_ivec2();
+        (min.x..=max.x).flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+    }
+
+    // indices of the entries on `layer` overlapping the box, each once
+    fn overlapping(&self, layer: CollisionLayer, min: Vec2, max: Vec2) -> Vec<usize> {
+        let mut seen = HashSet::new();
+        self.cells_between(min, max)
+            .filter_map(|cell| self.cells.get(&(layer, cell)))
+            .flatten()
+            .copied()
+            .filter(|&index| self.entries[index].overlaps(min, max) && seen.insert(index))
+            .collect()
+    }
+
+    /// Entities on `layer` whose bounds overlap the box from `min` to `max`
+    pub fn query(&self, layer: CollisionLayer, min: Vec2, max: Vec2) -> Vec<Entity> {
+        self.overlapping(layer, min, max)
+            .into_iter()
+            .map(|index| self.entries[index].entity)
+            .collect()
+    }
+
+    /// Pairs of entities on layers `a` and `b` with overlapping bounds, each pair once.
+    /// The first entity of a pair is on layer `a`.
+    pub fn candidate_pairs(&self, a: CollisionLayer, b: CollisionLayer) -> Vec<(Entity, Entity)> {
+        let layer_a = match self.layers.get(&a) {
+            Some(layer_a) => layer_a,
+            None => return Vec::new(),
+        };
+        let mut pairs = Vec::new();
+        for &index_a in layer_a {
+            let entry = &self.entries[index_a];
+            for index_b in self.overlapping(b, entry.min, entry.max) {
+                // on a single layer the pair is found from both of its entries
+                if a != b || index_a < index_b {
+                    pairs.push((entry.entity, self.entries[index_b].entity));
+                }
+            }
+        }
+        pairs
+    }
+}
+
+// swept shapes are boxed in along their whole path this tick
+fn update_broad_phase(
+    mut broad_phase: ResMut<BroadPhase>,
+    shapes: Query<(
+        Entity,
+        &Transform,
+        &CollisionLayer,
+        &CollisionBounds,
+        Option<&Velocity>,
+        Option<&Swept>,
+    )>,
+    fixed_time: Res<FixedTime>,
+) {
+    broad_phase.clear();
+    for (entity, transform, layer, bounds, velocity, swept) in shapes.iter() {
+        let end = transform.translation.truncate();
+        let start = match (velocity, swept) {
+            (Some(velocity), Some(_)) => end - velocity.0 * fixed_time.period.as_secs_f32(),
+            _ => end,
+        };
+        broad_phase.insert(
+            entity,
+            *layer,
+            (start + end) / 2.0,
+            (end - start).abs() / 2.0 + bounds.0,
+        );
+    }
+}
+
+pub struct BroadPhasePlugin;
+impl Plugin for BroadPhasePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BroadPhase>().add_system(
+            update_broad_phase
+                .in_set(UpdateCollisionShapes)
+                .in_schedule(CoreSchedule::FixedUpdate),
+        );
+    }
+}
@@ -1,6 +1,9 @@
 use crate::{
-    constants::{SCREEN_HEIGHT, SCREEN_WIDTH},
-    physics::{FixedOffset, Velocity},
+    physics::{
+        CollisionBounds, CollisionLayer, CollisionMask, DespawnWhenOffscreen, FixedOffset,
+        LeftScreen, SimulationSet, Swept, Velocity,
+    },
+    score::Score,
     GameState, LevelEntity,
 };
 use bevy::prelude::*;
@@ -10,10 +13,57 @@ use impacted::CollisionShape;
 const BULLET_SPEED: f32 = 500.0;
 
 #[derive(Component)]
-pub(crate) struct Bullet;
+pub struct Bullet;
+
+/// Who fired a bullet. Bullets only hit the other side.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BulletOwner {
+    Player,
+    Enemy,
+}
+
+impl BulletOwner {
+    pub fn bullet_layer(self) -> CollisionLayer {
+        match self {
+            BulletOwner::Player => CollisionLayer::PlayerBullet,
+            BulletOwner::Enemy => CollisionLayer::EnemyBullet,
+        }
+    }
+
+    pub fn bullet_mask(self) -> CollisionMask {
+        match self {
+            BulletOwner::Player => {
+                CollisionMask::new(&[CollisionLayer::Enemy, CollisionLayer::Wall])
+            }
+            BulletOwner::Enemy => {
+                CollisionMask::new(&[CollisionLayer::Player, CollisionLayer::Wall])
+            }
+        }
+    }
+
+    pub fn bullet_radius(self) -> f32 {
+        match self {
+            BulletOwner::Player => 4.0,
+            BulletOwner::Enemy => 3.0,
+        }
+    }
+}
 
 pub struct SpawnBullet {
+    /// position of the bullet, which flies in the direction the transform is pointing
     pub initial_transform: Transform,
+    pub owner: BulletOwner,
+    pub speed: f32,
+}
+
+impl SpawnBullet {
+    pub fn player(initial_transform: Transform) -> Self {
+        Self {
+            initial_transform,
+            owner: BulletOwner::Player,
+            speed: BULLET_SPEED,
+        }
+    }
 }
 
 #[derive(Component)]
@@ -42,8 +92,12 @@ impl BulletClip {
 }
 
 fn spawn_bullet(mut commands: Commands, mut spawn_event: EventReader<SpawnBullet>) {
-    let bullet_radius = 4.0;
     for ev in spawn_event.iter() {
+        let bullet_radius = ev.owner.bullet_radius();
+        let color = match ev.owner {
+            BulletOwner::Player => Color::rgb_u8(255, 255, 255),
+            BulletOwner::Enemy => Color::rgb_u8(120, 30, 60),
+        };
         let shape = shapes::Circle {
             radius: bullet_radius,
             ..Default::default()
@@ -51,36 +105,40 @@ fn spawn_bullet(mut commands: Commands, mut spawn_event: EventReader<SpawnBullet
         // calculate velocity vector based on rotation of character
         let (axis, angle) = ev.initial_transform.rotation.to_axis_angle();
         let direction = Vec2::new(-axis.z * f32::sin(angle), f32::cos(angle));
-        let velocity = Velocity(BULLET_SPEED * direction);
-        commands.spawn((
+        let velocity = Velocity(ev.speed * direction);
+        let mut bullet = commands.spawn((
             ShapeBundle {
                 path: GeometryBuilder::build_as(&shape),
                 transform: ev.initial_transform,
                 ..default()
             },
-            Fill::color(Color::rgb_u8(255, 255, 255)),
+            Fill::color(color),
             Bullet,
+            ev.owner,
             velocity,
             CollisionShape::new_circle(bullet_radius),
+            ev.owner.bullet_layer(),
+            ev.owner.bullet_mask(),
+            CollisionBounds(Vec2::splat(bullet_radius)),
+            DespawnWhenOffscreen { margin: 0.0 },
             LevelEntity,
         ));
+        // player bullets are fast enough to skip over enemies between ticks
+        if ev.owner == BulletOwner::Player {
+            bullet.insert(Swept);
+        }
     }
 }
 
-fn despawn_bullet(
-    mut commands: Commands,
-    bullets: Query<(Entity, &Transform), With<Bullet>>,
-    camera: Query<&Transform, (With<Camera>, With<Velocity>)>,
+// player bullets that leave the screen missed
+fn break_combo_on_miss(
+    mut left_screen: EventReader<LeftScreen>,
+    bullets: Query<&BulletOwner, With<Bullet>>,
+    mut score: ResMut<Score>,
 ) {
-    let camera_transform = camera.single();
-    for (e, t) in bullets.iter() {
-        let t = t.translation - camera_transform.translation.y * Vec3::Y;
-        if t.x > SCREEN_WIDTH / 2.0
-            || t.x < -SCREEN_WIDTH / 2.0
-            || t.y > SCREEN_HEIGHT / 2.0
-            || t.y < -SCREEN_HEIGHT / 2.0
-        {
-            commands.entity(e).despawn();
+    for LeftScreen(entity) in left_screen.iter() {
+        if matches!(bullets.get(*entity), Ok(BulletOwner::Player)) {
+            score.break_combo();
         }
     }
 }
@@ -153,10 +211,12 @@ pub struct BulletPlugin;
 impl Plugin for BulletPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<SpawnBullet>()
-            .add_system(spawn_bullet_clip.in_schedule(OnExit(GameState::Menu)))
-            .add_systems(
-                (spawn_bullet, despawn_bullet, update_bullet_clip)
-                    .in_set(OnUpdate(GameState::Playing)),
+            .add_system(spawn_bullet_clip.in_schedule(OnEnter(GameState::LoadLevel)))
+            .add_systems((spawn_bullet, update_bullet_clip).in_set(OnUpdate(GameState::Playing)))
+            .add_system(
+                break_combo_on_miss
+                    .in_set(SimulationSet::Gameplay)
+                    .in_schedule(CoreSchedule::FixedUpdate),
             );
     }
 }
@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::bullet::{BulletOwner, SpawnBullet};
+use crate::constants::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::enemy::Enemy;
+use crate::physics::{SimulationSet, Velocity};
+use crate::player::Player;
+
+/// How an enemy archetype shoots
+#[derive(Deserialize, Debug, Clone)]
+pub struct FirePattern {
+    /// seconds between shots
+    pub interval: f32,
+    pub bullet_speed: f32,
+    pub emitter: Emitter,
+}
+
+/// Directions of the bullets in a single shot. Angles are in degrees.
+#[derive(Deserialize, Debug, Clone)]
+pub enum Emitter {
+    /// one bullet at the player
+    Aimed,
+    /// `count` bullets evenly spaced in a circle
+    Radial { count: u32 },
+    /// `arms` bullets evenly spaced in a circle, turning by `turn` every shot
+    Spiral { arms: u32, turn: f32 },
+    /// `count` bullets fanned out over `angle`, centered on the player
+    Spread { count: u32, angle: f32 },
+}
+
+impl Emitter {
+    /// Bullet directions for the `shot`th shot, with `aim` pointing at the player
+    pub fn directions(&self, aim: Vec2, shot: u32) -> Vec<Vec2> {
+        let ring = |count: u32, start: f32| -> Vec<Vec2> {
+            let step = std::f32::consts::TAU / count.max(1) as f32;
+            (0..count)
+                .map(|i| Vec2::from_angle(start + step * i as f32).rotate(Vec2::NEG_Y))
+                .collect()
+        };
+        match *self {
+            Emitter::Aimed => vec![aim],
+            Emitter::Radial { count } => ring(count, 0.0),
+            Emitter::Spiral { arms, turn } => ring(arms, (turn * shot as f32).to_radians()),
+            Emitter::Spread { count, angle } => {
+                if count <= 1 {
+                    return vec![aim];
+                }
+                let step = angle.to_radians() / (count - 1) as f32;
+                let start = -angle.to_radians() / 2.0;
+                (0..count)
+                    .map(|i| Vec2::from_angle(start + step * i as f32).rotate(aim))
+                    .collect()
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct EnemyGun {
+    pattern: FirePattern,
+    timer: Timer,
+    shots: u32,
+}
+
+impl EnemyGun {
+    pub fn new(pattern: FirePattern) -> Self {
+        Self {
+            timer: Timer::from_seconds(pattern.interval, TimerMode::Repeating),
+            pattern,
+            shots: 0,
+        }
+    }
+}
+
+// enemies only shoot while they are on screen
+fn enemy_shoot(
+    mut guns: Query<(&Transform, &mut EnemyGun), With<Enemy>>,
+    player: Query<&Transform, (With<Player>, Without<Enemy>)>,
+    camera: Query<&Transform, (With<Camera>, With<Velocity>, Without<Enemy>)>,
+    mut spawn_bullet: EventWriter<SpawnBullet>,
+    fixed_time: Res<FixedTime>,
+) {
+    if player.is_empty() {
+        return;
+    }
+    let player = player.single();
+    let camera = camera.single().translation;
+    for (transform, mut gun) in guns.iter_mut() {
+        let on_screen = (transform.translation - camera).truncate().abs();
+        if on_screen.x > SCREEN_WIDTH / 2.0 || on_screen.y > SCREEN_HEIGHT / 2.0 {
+            continue;
+        }
+        if !gun.timer.tick(fixed_time.period).just_finished() {
+            continue;
+        }
+
+        let aim = (player.translation - transform.translation)
+            .truncate()
+            .try_normalize()
+            .unwrap_or(Vec2::NEG_Y);
+        for direction in gun.pattern.emitter.directions(aim, gun.shots) {
+            spawn_bullet.send(SpawnBullet {
+                initial_transform: Transform::from_translation(transform.translation)
+                    .with_rotation(Quat::from_rotation_z(Vec2::Y.angle_between(direction))),
+                owner: BulletOwner::Enemy,
+                speed: gun.pattern.bullet_speed,
+            });
+        }
+        gun.shots += 1;
+    }
+}
+
+pub struct BulletPatternPlugin;
+impl Plugin for BulletPatternPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            enemy_shoot
+                .in_set(SimulationSet::Gameplay)
+                .in_schedule(CoreSchedule::FixedUpdate),
+        );
+    }
+}
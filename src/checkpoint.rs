@@ -0,0 +1,221 @@
+use bevy::prelude::*;
+
+use crate::lives::{Lives, STARTING_LIVES};
+use crate::loading::FontAssets;
+use crate::menu::{ButtonColors, ButtonInteraction};
+use crate::physics::Velocity;
+use crate::replay::ReplayMode;
+use crate::score::Score;
+use crate::serialize::{level_scene_ron, spawn_scene};
+use crate::{GameState, LevelEntity};
+
+/// Once the camera scrolls past this marker, losing the last life offers to continue from here
+/// instead of starting the level over
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct Checkpoint;
+
+/// The level as it was when the last checkpoint was reached, kept until the level ends
+#[derive(Resource)]
+pub(crate) struct ReachedCheckpoint {
+    camera_y: f32,
+    // a scene like a level file, without the enemies destroyed before the checkpoint
+    scene: String,
+    points: u32,
+}
+
+#[derive(Component)]
+struct ContinueMenu;
+
+#[derive(Component)]
+struct ContinueButton;
+
+fn after_deserialize_checkpoint(
+    mut commands: Commands,
+    q: Query<Entity, (With<Checkpoint>, Without<LevelEntity>)>,
+) {
+    for entity in q.iter() {
+        commands.entity(entity).insert(LevelEntity);
+    }
+}
+
+fn forget_checkpoint(mut commands: Commands) {
+    commands.remove_resource::<ReachedCheckpoint>();
+}
+
+fn reach_checkpoint(world: &mut World) {
+    // replays cover a single attempt at a level
+    if world.contains_resource::<ReplayMode>() {
+        return;
+    }
+    let camera_y = world
+        .query_filtered::<&Transform, (With<Camera>, With<Velocity>)>()
+        .single(world)
+        .translation
+        .y;
+    let reached: Vec<(Entity, f32)> = world
+        .query_filtered::<(Entity, &Transform), With<Checkpoint>>()
+        .iter(world)
+        .filter(|(_, transform)| camera_y >= transform.translation.y)
+        .map(|(entity, transform)| (entity, transform.translation.y))
+        .collect();
+    let Some(checkpoint_y) = reached.iter().map(|(_, y)| *y).reduce(f32::max) else {
+        return;
+    };
+    // passed checkpoints are left out of the scene, so they aren't reached again
+    for (entity, _) in reached {
+        world.despawn(entity);
+    }
+
+    match level_scene_ron(world) {
+        Ok(scene) => {
+            let points = world.resource::<Score>().points;
+            world.insert_resource(ReachedCheckpoint {
+                camera_y: checkpoint_y,
+                scene,
+                points,
+            });
+        }
+        Err(error) => warn!("failed to save checkpoint: {error}"),
+    }
+}
+
+// Loads the level from the checkpoint instead of its file, with the camera at the checkpoint,
+// the score as it was there and a full set of lives.
+fn load_checkpoint(world: &mut World) {
+    let checkpoint = world.resource::<ReachedCheckpoint>();
+    let (camera_y, scene, points) = (
+        checkpoint.camera_y,
+        checkpoint.scene.clone(),
+        checkpoint.points,
+    );
+    if let Err(error) = spawn_scene(world, &scene) {
+        warn!("failed to load checkpoint: {error}");
+    }
+    world
+        .query_filtered::<&mut Transform, (With<Camera>, With<Velocity>)>()
+        .single_mut(world)
+        .translation
+        .y = camera_y;
+    *world.resource_mut::<Score>() = Score {
+        points,
+        ..default()
+    };
+    world.resource_mut::<Lives>().0 = STARTING_LIVES;
+    world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::PostLoadLevel);
+}
+
+fn setup_continue_menu(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+) {
+    let text_style = TextStyle {
+        font: font_assets.fira_sans.clone(),
+        font_size: 40.0,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                background_color: Color::NONE.into(),
+                ..Default::default()
+            },
+            ContinueMenu,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(160.0), Val::Px(50.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        background_color: button_colors.normal,
+                        ..Default::default()
+                    },
+                    ContinueButton,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section("Continue", text_style.clone()));
+                });
+            parent.spawn(TextBundle::from_section(
+                "Esc: menu",
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::rgb(0.15, 0.15, 0.15),
+                    ..text_style
+                },
+            ));
+        });
+}
+
+fn click_continue_button(
+    button_colors: Res<ButtonColors>,
+    mut state: ResMut<NextState<GameState>>,
+    mut interaction_query: Query<ButtonInteraction, (Changed<Interaction>, With<ContinueButton>)>,
+) {
+    for (_button, interaction, mut color, _children) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                state.set(GameState::LoadLevel);
+            }
+            Interaction::Hovered => {
+                *color = button_colors.hovered;
+            }
+            Interaction::None => {
+                *color = button_colors.normal;
+            }
+        }
+    }
+}
+
+fn give_up(keyboard_input: Res<Input<KeyCode>>, mut state: ResMut<NextState<GameState>>) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        state.set(GameState::Menu);
+    }
+}
+
+fn despawn_continue_menu(mut commands: Commands, q: Query<Entity, With<ContinueMenu>>) {
+    for e in q.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+pub struct CheckpointPlugin;
+impl Plugin for CheckpointPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Checkpoint>()
+            .add_system(after_deserialize_checkpoint.in_set(OnUpdate(GameState::PostLoadLevel)))
+            .add_system(reach_checkpoint.in_set(OnUpdate(GameState::Playing)))
+            .add_system(forget_checkpoint.in_schedule(OnEnter(GameState::Menu)))
+            .add_system(forget_checkpoint.in_schedule(OnEnter(GameState::LevelFinished)))
+            .add_system(
+                load_checkpoint
+                    .run_if(resource_exists::<ReachedCheckpoint>())
+                    .in_schedule(OnEnter(GameState::LoadLevel)),
+            )
+            .add_system(
+                setup_continue_menu
+                    .run_if(resource_exists::<ReachedCheckpoint>())
+                    .in_schedule(OnEnter(GameState::PlayerDead)),
+            )
+            .add_systems(
+                (click_continue_button, give_up)
+                    .distributive_run_if(resource_exists::<ReachedCheckpoint>())
+                    .in_set(OnUpdate(GameState::PlayerDead)),
+            )
+            .add_system(despawn_continue_menu.in_schedule(OnExit(GameState::PlayerDead)));
+    }
+}
@@ -0,0 +1,177 @@
+use bevy::prelude::*;
+
+use crate::input_map::{
+    save_input_map, Binding, InputAction, InputDevices, InputMap, InputMapFile,
+};
+use crate::loading::FontAssets;
+use crate::GameState;
+
+// how much - and = change the keyboard aim rotation speed, in degrees per second
+const AIM_ROTATION_SPEED_STEP: f32 = 30.0;
+const MAX_AIM_ROTATION_SPEED: f32 = 720.0;
+
+// the action being looked at on the controls screen
+#[derive(Resource, Default)]
+struct ControlsMenu {
+    selected: usize,
+    // the next button pressed gets bound to the selected action
+    waiting: bool,
+    message: String,
+}
+
+#[derive(Component)]
+struct ControlsText;
+
+fn open_controls(keyboard_input: Res<Input<KeyCode>>, mut state: ResMut<NextState<GameState>>) {
+    if keyboard_input.just_pressed(KeyCode::C) {
+        state.set(GameState::Controls);
+    }
+}
+
+fn setup_controls_menu(mut commands: Commands, font_assets: Res<FontAssets>) {
+    commands.insert_resource(ControlsMenu::default());
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: font_assets.fira_sans.clone(),
+                    font_size: 20.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(5.0),
+                    top: Val::Px(5.0),
+                    ..default()
+                },
+                ..default()
+            },
+            ..default()
+        },
+        ControlsText,
+    ));
+}
+
+// One system for all of the screen's keys, so the Enter that starts waiting for a binding
+// isn't taken as the binding too.
+fn edit_controls(
+    mut menu: ResMut<ControlsMenu>,
+    mut input_map: ResMut<InputMap>,
+    file: Res<InputMapFile>,
+    devices: InputDevices,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    let keys = &devices.keys;
+    let action = InputAction::ALL[menu.selected];
+    if menu.waiting {
+        if keys.just_pressed(KeyCode::Escape) {
+            menu.waiting = false;
+            menu.message.clear();
+            return;
+        }
+        let Some(binding) = devices.any_just_pressed() else {
+            return;
+        };
+        menu.waiting = false;
+        match input_map.bind(action, binding) {
+            Ok(()) => menu.message.clear(),
+            Err(other) => {
+                menu.message = format!("{} is already bound to {other:?}", binding_name(binding));
+                return;
+            }
+        }
+    } else if keys.just_pressed(KeyCode::Escape) {
+        state.set(GameState::Menu);
+        return;
+    } else if keys.just_pressed(KeyCode::Up) {
+        menu.selected = menu.selected.saturating_sub(1);
+        return;
+    } else if keys.just_pressed(KeyCode::Down) {
+        menu.selected = (menu.selected + 1).min(InputAction::ALL.len() - 1);
+        return;
+    } else if keys.just_pressed(KeyCode::Return) {
+        menu.waiting = true;
+        menu.message = format!("press a button for {action:?}, Esc cancels");
+        return;
+    } else if keys.just_pressed(KeyCode::Back) {
+        input_map.clear(action);
+    } else if keys.just_pressed(KeyCode::R) {
+        *input_map = InputMap::default();
+    } else if keys.just_pressed(KeyCode::M) {
+        input_map.aim_mode = input_map.aim_mode.next();
+    } else if keys.just_pressed(KeyCode::Minus) {
+        input_map.aim_rotation_speed =
+            (input_map.aim_rotation_speed - AIM_ROTATION_SPEED_STEP).max(AIM_ROTATION_SPEED_STEP);
+    } else if keys.just_pressed(KeyCode::Equals) {
+        input_map.aim_rotation_speed =
+            (input_map.aim_rotation_speed + AIM_ROTATION_SPEED_STEP).min(MAX_AIM_ROTATION_SPEED);
+    } else {
+        return;
+    }
+
+    if let Err(error) = save_input_map(&file, &input_map) {
+        menu.message = format!("could not save controls: {error}");
+    }
+}
+
+fn binding_name(binding: Binding) -> String {
+    match binding {
+        Binding::Key(key) => format!("{key:?}"),
+        Binding::Mouse(button) => format!("Mouse {button:?}"),
+        Binding::Gamepad(button_type) => format!("Gamepad {button_type:?}"),
+    }
+}
+
+fn update_controls_text(
+    menu: Res<ControlsMenu>,
+    input_map: Res<InputMap>,
+    mut text: Query<&mut Text, With<ControlsText>>,
+) {
+    if !menu.is_changed() && !input_map.is_changed() {
+        return;
+    }
+    let mut value = String::from(
+        "Up/Down selects, Enter adds a binding, Backspace clears, R resets, Esc goes back\n\n",
+    );
+    for (i, action) in InputAction::ALL.into_iter().enumerate() {
+        let cursor = if i == menu.selected { ">" } else { " " };
+        let bindings: Vec<String> = input_map
+            .bindings(action)
+            .iter()
+            .map(|binding| binding_name(*binding))
+            .collect();
+        value += &format!("{cursor} {action:?}: {}\n", bindings.join(", "));
+    }
+    value += &format!(
+        "\naim mode {:?} (M), keyboard aim turns {} degrees per second (- and =)\n",
+        input_map.aim_mode, input_map.aim_rotation_speed,
+    );
+    value += &format!("\n{}", menu.message);
+    for mut text in text.iter_mut() {
+        text.sections[0].value = value.clone();
+    }
+}
+
+fn despawn_controls_menu(mut commands: Commands, q: Query<Entity, With<ControlsText>>) {
+    commands.remove_resource::<ControlsMenu>();
+    for e in q.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+pub struct ControlsMenuPlugin;
+impl Plugin for ControlsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(open_controls.in_set(OnUpdate(GameState::Menu)))
+            .add_system(setup_controls_menu.in_schedule(OnEnter(GameState::Controls)))
+            .add_systems(
+                (edit_controls, update_controls_text)
+                    .chain()
+                    .in_set(OnUpdate(GameState::Controls)),
+            )
+            .add_system(despawn_controls_menu.in_schedule(OnExit(GameState::Controls)));
+    }
+}
@@ -0,0 +1,478 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use bevy::input::mouse::MouseWheel;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::enemy::{build_enemy, Enemy, EnemyArchetypes, EnemyKind, DEFAULT_ARCHETYPE};
+use crate::level::LevelManifest;
+use crate::loading::{EnemyAssets, FontAssets, LevelAssets};
+use crate::physics::{CollisionBounds, Velocity};
+use crate::playfield::cursor_to_world;
+use crate::serialize::{spawn_scene, SaveSceneEvent};
+use crate::GameState;
+
+// level paths are relative to the assets folder, like the asset server's
+const ASSETS_DIR: &str = "assets";
+// camera speed with the arrow keys, in pixels per second
+const SCROLL_SPEED: f32 = 300.0;
+// pixels scrolled per line of mouse wheel movement
+const WHEEL_SCROLL: f32 = 20.0;
+const GRID_SIZE: f32 = 10.0;
+
+/// The level open in the editor. Inserting this resource while in the menu opens the editor,
+/// pressing E in the menu opens it on the level chosen with Left and Right.
+#[derive(Resource, Debug)]
+pub struct EditorLevel {
+    /// path of the scene file, relative to the assets folder like the `LevelManifest`'s paths.
+    /// Created when saving if it doesn't exist yet.
+    pub path: PathBuf,
+    /// archetype of newly placed enemies
+    pub kind: String,
+    /// size of the grid placed and dragged enemies snap to, if any
+    pub grid: Option<f32>,
+}
+
+impl EditorLevel {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        EditorLevel {
+            path: path.into(),
+            kind: DEFAULT_ARCHETYPE.to_string(),
+            grid: None,
+        }
+    }
+
+    pub fn snap(&self, point: Vec2) -> Vec2 {
+        match self.grid {
+            Some(size) => (point / size).round() * size,
+            None => point,
+        }
+    }
+
+    fn file(&self) -> PathBuf {
+        Path::new(ASSETS_DIR).join(&self.path)
+    }
+}
+
+/// World position of the cursor while editing, `None` when it is outside the window
+#[derive(Resource, Default, Debug)]
+pub struct EditorCursor(pub Option<Vec2>);
+
+#[derive(Component)]
+struct Selected;
+
+/// Follows the cursor at `offset` from it until the mouse button is released
+#[derive(Component)]
+struct Dragged {
+    offset: Vec2,
+}
+
+#[derive(Component)]
+struct EditorText;
+
+/// Index into the `LevelManifest` of the level pressing E in the menu opens
+#[derive(Resource, Default, Debug)]
+pub struct ChosenLevel(pub usize);
+
+#[derive(Component)]
+struct ChosenLevelText;
+
+fn ctrl_pressed(keyboard_input: &Input<KeyCode>) -> bool {
+    keyboard_input.any_pressed([KeyCode::LControl, KeyCode::RControl])
+}
+
+fn save_pressed(keyboard_input: Res<Input<KeyCode>>) -> bool {
+    ctrl_pressed(&keyboard_input) && keyboard_input.just_pressed(KeyCode::S)
+}
+
+fn open_editor(
+    mut commands: Commands,
+    editor: Option<Res<EditorLevel>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    level_assets: Res<LevelAssets>,
+    manifests: Res<Assets<LevelManifest>>,
+    chosen_level: Res<ChosenLevel>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    if editor.is_some() {
+        state.set(GameState::LoadLevel);
+    } else if keyboard_input.just_pressed(KeyCode::E) {
+        let manifest = manifests.get(&level_assets.manifest).unwrap();
+        let path = &manifest.levels[chosen_level.0].path;
+        commands.insert_resource(EditorLevel::new(path));
+    }
+}
+
+fn choose_level(
+    keyboard_input: Res<Input<KeyCode>>,
+    level_assets: Res<LevelAssets>,
+    manifests: Res<Assets<LevelManifest>>,
+    mut chosen_level: ResMut<ChosenLevel>,
+) {
+    let count = manifests.get(&level_assets.manifest).unwrap().levels.len();
+    if keyboard_input.just_pressed(KeyCode::Right) {
+        chosen_level.0 = (chosen_level.0 + 1) % count;
+    }
+    if keyboard_input.just_pressed(KeyCode::Left) {
+        chosen_level.0 = (chosen_level.0 + count - 1) % count;
+    }
+}
+
+fn chosen_level_text(manifest: &LevelManifest, chosen_level: &ChosenLevel) -> String {
+    format!(
+        "editing {} (Left/Right)",
+        manifest.levels[chosen_level.0].name
+    )
+}
+
+// shown in the menu just above its hint
+fn setup_chosen_level_text(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    level_assets: Res<LevelAssets>,
+    manifests: Res<Assets<LevelManifest>>,
+    chosen_level: Res<ChosenLevel>,
+) {
+    let manifest = manifests.get(&level_assets.manifest).unwrap();
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section(
+                chosen_level_text(manifest, &chosen_level),
+                TextStyle {
+                    font: font_assets.fira_sans.clone(),
+                    font_size: 20.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(5.0),
+                    bottom: Val::Px(30.0),
+                    ..default()
+                },
+                ..default()
+            },
+            ..default()
+        },
+        ChosenLevelText,
+    ));
+}
+
+fn update_chosen_level_text(
+    chosen_level: Res<ChosenLevel>,
+    level_assets: Res<LevelAssets>,
+    manifests: Res<Assets<LevelManifest>>,
+    mut text: Query<&mut Text, With<ChosenLevelText>>,
+) {
+    if !chosen_level.is_changed() {
+        return;
+    }
+    let manifest = manifests.get(&level_assets.manifest).unwrap();
+    for mut text in text.iter_mut() {
+        text.sections[0].value = chosen_level_text(manifest, &chosen_level);
+    }
+}
+
+fn despawn_chosen_level_text(mut commands: Commands, q: Query<Entity, With<ChosenLevelText>>) {
+    for e in q.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+// Reads the file directly rather than through the asset server,
+// which would keep returning the version it loaded first.
+fn load_level(world: &mut World) {
+    let file = world.resource::<EditorLevel>().file();
+    match fs::read_to_string(&file) {
+        Ok(contents) => {
+            if let Err(error) = spawn_scene(world, &contents) {
+                warn!("failed to load level {file:?}: {error}");
+            }
+        }
+        Err(error) if error.kind() == ErrorKind::NotFound => info!("new level {file:?}"),
+        Err(error) => warn!("failed to read level {file:?}: {error}"),
+    }
+    world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::PostLoadLevel);
+}
+
+// levels without enemies are ready straight away
+fn finish_loading(mut state: ResMut<NextState<GameState>>) {
+    state.set(GameState::Editor);
+}
+
+fn save_level(editor: Res<EditorLevel>, mut save_scene: EventWriter<SaveSceneEvent>) {
+    save_scene.send(SaveSceneEvent {
+        path: editor.file(),
+    });
+}
+
+fn setup_editor_text(mut commands: Commands, font_assets: Res<FontAssets>) {
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: font_assets.fira_sans.clone(),
+                    font_size: 14.0,
+                    color: Color::rgb(0.15, 0.15, 0.15),
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(5.0),
+                    top: Val::Px(5.0),
+                    ..default()
+                },
+                ..default()
+            },
+            ..default()
+        },
+        EditorText,
+    ));
+}
+
+fn update_editor_text(editor: Res<EditorLevel>, mut text: Query<&mut Text, With<EditorText>>) {
+    if !editor.is_changed() {
+        return;
+    }
+    let grid = editor
+        .grid
+        .map_or("off".to_string(), |size| size.to_string());
+    for mut text in text.iter_mut() {
+        text.sections[0].value = format!(
+            "{}\nplacing {} (Tab), grid {grid} (G)\n\
+             right click or Delete removes, Ctrl+S saves, Ctrl+O reloads, Esc exits",
+            editor.path.display(),
+            editor.kind,
+        );
+    }
+}
+
+fn despawn_editor_text(mut commands: Commands, q: Query<Entity, With<EditorText>>) {
+    for e in q.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+// the camera only scrolls by itself while playing
+fn scroll_camera(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut mouse_wheel: EventReader<MouseWheel>,
+    mut camera: Query<&mut Transform, (With<Camera>, With<Velocity>)>,
+    time: Res<Time>,
+) {
+    let mut scroll = mouse_wheel
+        .iter()
+        .map(|wheel| wheel.y * WHEEL_SCROLL)
+        .sum::<f32>();
+    if keyboard_input.pressed(KeyCode::Up) {
+        scroll += SCROLL_SPEED * time.delta_seconds();
+    }
+    if keyboard_input.pressed(KeyCode::Down) {
+        scroll -= SCROLL_SPEED * time.delta_seconds();
+    }
+    camera.single_mut().translation.y += scroll;
+}
+
+// headless apps have no window, whoever drives them sets the cursor directly
+fn update_cursor(
+    mut cursor: ResMut<EditorCursor>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    camera: Query<(&Camera, &GlobalTransform), With<Velocity>>,
+) {
+    let window = match window.get_single() {
+        Ok(window) => window,
+        Err(_) => return,
+    };
+    let (camera, camera_transform) = camera.single();
+    cursor.0 = cursor_to_world(window, camera, camera_transform);
+}
+
+// the enemy closest to `point` whose bounds contain it
+fn enemy_at(
+    enemies: &Query<(Entity, &Transform, &CollisionBounds), With<Enemy>>,
+    point: Vec2,
+) -> Option<(Entity, Vec2)> {
+    enemies
+        .iter()
+        .map(|(entity, transform, bounds)| (entity, transform.translation.truncate(), bounds))
+        .filter(|(_, center, bounds)| (point - *center).abs().cmple(bounds.0).all())
+        .min_by(|(_, a, _), (_, b, _)| a.distance(point).total_cmp(&b.distance(point)))
+        .map(|(entity, center, _)| (entity, center))
+}
+
+// clicking an enemy selects it, clicking anywhere else places a new one
+fn select_or_place_enemy(
+    mut commands: Commands,
+    editor: Res<EditorLevel>,
+    cursor: Res<EditorCursor>,
+    mouse_button: Res<Input<MouseButton>>,
+    enemies: Query<(Entity, &Transform, &CollisionBounds), With<Enemy>>,
+    selected: Query<Entity, With<Selected>>,
+    enemy_assets: Res<EnemyAssets>,
+    archetypes: Res<Assets<EnemyArchetypes>>,
+) {
+    let cursor = match cursor.0 {
+        Some(cursor) if mouse_button.just_pressed(MouseButton::Left) => cursor,
+        _ => return,
+    };
+    for entity in selected.iter() {
+        commands.entity(entity).remove::<(Selected, Stroke)>();
+    }
+
+    let (entity, offset) = match enemy_at(&enemies, cursor) {
+        Some((entity, center)) => (entity, center - cursor),
+        None => {
+            let archetypes = &archetypes.get(&enemy_assets.archetypes).unwrap().archetypes;
+            let archetype = match archetypes.get(&editor.kind) {
+                Some(archetype) => archetype,
+                None => {
+                    warn!("unknown enemy archetype {}", editor.kind);
+                    return;
+                }
+            };
+            let transform = Transform::from_translation(editor.snap(cursor).extend(1.0));
+            let mut enemy = commands.spawn((Enemy, EnemyKind(editor.kind.clone())));
+            build_enemy(&mut enemy, transform, archetype);
+            (enemy.id(), Vec2::ZERO)
+        }
+    };
+    commands.entity(entity).insert((
+        Selected,
+        Stroke::new(Color::rgb_u8(40, 40, 40), 2.0),
+        Dragged { offset },
+    ));
+}
+
+fn drag_enemy(
+    mut commands: Commands,
+    editor: Res<EditorLevel>,
+    cursor: Res<EditorCursor>,
+    mouse_button: Res<Input<MouseButton>>,
+    mut dragged: Query<(Entity, &mut Transform, &Dragged)>,
+) {
+    for (entity, mut transform, dragged) in dragged.iter_mut() {
+        if let Some(cursor) = cursor.0 {
+            let z = transform.translation.z;
+            transform.translation = editor.snap(cursor + dragged.offset).extend(z);
+        }
+        if !mouse_button.pressed(MouseButton::Left) {
+            commands.entity(entity).remove::<Dragged>();
+        }
+    }
+}
+
+fn delete_enemies(
+    mut commands: Commands,
+    cursor: Res<EditorCursor>,
+    mouse_button: Res<Input<MouseButton>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    enemies: Query<(Entity, &Transform, &CollisionBounds), With<Enemy>>,
+    selected: Query<Entity, With<Selected>>,
+) {
+    if mouse_button.just_pressed(MouseButton::Right) {
+        if let Some((entity, _)) = cursor.0.and_then(|cursor| enemy_at(&enemies, cursor)) {
+            commands.entity(entity).despawn();
+        }
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Delete, KeyCode::Back]) {
+        for entity in selected.iter() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn editor_settings(
+    mut editor: ResMut<EditorLevel>,
+    keyboard_input: Res<Input<KeyCode>>,
+    enemy_assets: Res<EnemyAssets>,
+    archetypes: Res<Assets<EnemyArchetypes>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::G) {
+        editor.grid = match editor.grid {
+            Some(_) => None,
+            None => Some(GRID_SIZE),
+        };
+    }
+    if keyboard_input.just_pressed(KeyCode::Tab) {
+        let archetypes = &archetypes.get(&enemy_assets.archetypes).unwrap().archetypes;
+        let mut names: Vec<_> = archetypes.keys().collect();
+        names.sort();
+        let next = names
+            .iter()
+            .position(|name| **name == editor.kind)
+            .map_or(0, |index| (index + 1) % names.len());
+        editor.kind = names[next].clone();
+    }
+}
+
+fn leave_editor(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        commands.remove_resource::<EditorLevel>();
+        state.set(GameState::Menu);
+    } else if ctrl_pressed(&keyboard_input) && keyboard_input.just_pressed(KeyCode::O) {
+        // the level is cleaned up when leaving the editor, then loaded again
+        state.set(GameState::LoadLevel);
+    }
+}
+
+pub struct EditorPlugin;
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EditorCursor>()
+            .init_resource::<ChosenLevel>()
+            .add_system(setup_chosen_level_text.in_schedule(OnEnter(GameState::Menu)))
+            .add_systems(
+                (
+                    choose_level,
+                    update_chosen_level_text.after(choose_level),
+                    open_editor.after(choose_level),
+                )
+                    .in_set(OnUpdate(GameState::Menu)),
+            )
+            .add_system(despawn_chosen_level_text.in_schedule(OnExit(GameState::Menu)))
+            .add_system(
+                load_level
+                    .run_if(resource_exists::<EditorLevel>())
+                    .in_schedule(OnEnter(GameState::LoadLevel)),
+            )
+            .add_system(
+                finish_loading
+                    .run_if(resource_exists::<EditorLevel>())
+                    .in_set(OnUpdate(GameState::PostLoadLevel)),
+            )
+            .add_system(setup_editor_text.in_schedule(OnEnter(GameState::Editor)))
+            .add_system(despawn_editor_text.in_schedule(OnExit(GameState::Editor)))
+            .add_systems(
+                (
+                    update_cursor,
+                    select_or_place_enemy,
+                    drag_enemy,
+                    delete_enemies,
+                )
+                    .chain()
+                    .in_set(OnUpdate(GameState::Editor)),
+            )
+            .add_systems(
+                (
+                    scroll_camera,
+                    editor_settings,
+                    update_editor_text.after(editor_settings),
+                    save_level.run_if(save_pressed),
+                    leave_editor,
+                )
+                    .in_set(OnUpdate(GameState::Editor)),
+            );
+    }
+}
@@ -1,17 +1,99 @@
-use crate::bullet::Bullet;
-use crate::loading::AudioAssets;
-use crate::physics::UpdateCollisionShapes;
+use std::collections::{HashMap, HashSet};
+use std::f32::consts::TAU;
+
+use crate::bullet::{Bullet, BulletOwner};
+use crate::bullet_pattern::{EnemyGun, FirePattern};
+use crate::editor::EditorLevel;
+use crate::loading::{AudioAssets, EnemyAssets};
+use crate::physics::{
+    CollisionBounds, CollisionEvent, CollisionLayer, CollisionMask, DespawnWhenOffscreen,
+    Interpolated, SimulationSet, Velocity,
+};
+use crate::player::Player;
+use crate::ron_asset::RonAssetLoader;
+use crate::score::Score;
 use crate::{GameState, LevelEntity};
+use bevy::ecs::system::EntityCommands;
 use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
 use bevy_kira_audio::prelude::*;
 use bevy_prototype_lyon::prelude::*;
 use impacted::CollisionShape;
+use serde::Deserialize;
+
+// archetype used for enemies without an EnemyKind
+pub(crate) const DEFAULT_ARCHETYPE: &str = "grunt";
 
-const ENEMY_LENGTH: f32 = 30.;
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct Enemy;
 
+/// Name of the archetype in `enemies/archetypes.enemies.ron` an enemy is built from
 #[derive(Component, Default, Reflect)]
 #[reflect(Component)]
-pub(crate) struct Enemy;
+pub struct EnemyKind(pub String);
+
+/// All enemy archetypes by name
+#[derive(Deserialize, TypeUuid, Debug)]
+#[uuid = "d6f0a3c2-8b1e-4f57-a0c9-3e7b2d94f615"]
+pub struct EnemyArchetypes {
+    pub archetypes: HashMap<String, EnemyArchetype>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct EnemyArchetype {
+    /// width and height
+    pub size: (f32, f32),
+    pub shape: EnemyShape,
+    pub color: (u8, u8, u8),
+    pub hit_points: u32,
+    /// points awarded for destroying the enemy
+    pub score: u32,
+    #[serde(default)]
+    pub movement: MovementPattern,
+    /// enemies without a fire pattern don't shoot
+    #[serde(default)]
+    pub fire: Option<FirePattern>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub enum EnemyShape {
+    Rectangle,
+    Circle,
+    // pointing down towards the player
+    Triangle,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub enum MovementPattern {
+    #[default]
+    Stationary,
+    /// sways side to side around the spawn point
+    Sine { amplitude: f32, period: f32 },
+    /// flies straight at the player once the player is within `range` below it
+    Dive { speed: f32, range: f32 },
+    /// circles counterclockwise, starting at the spawn point
+    Orbit { radius: f32, period: f32 },
+    /// follows points relative to the spawn point, going back to the start when `looping`
+    FollowPath {
+        points: Vec<(f32, f32)>,
+        speed: f32,
+        looping: bool,
+    },
+}
+
+#[derive(Component)]
+pub struct HitPoints(pub u32);
+
+#[derive(Component)]
+pub struct ScoreValue(pub u32);
+
+#[derive(Component)]
+pub struct EnemyMovement {
+    pattern: MovementPattern,
+    origin: Vec2,
+    elapsed: f32,
+}
 
 #[derive(Bundle)]
 pub struct EnemyBundle {
@@ -20,64 +102,233 @@ pub struct EnemyBundle {
     #[bundle]
     shape_bundle: ShapeBundle,
     collision_shape: CollisionShape,
+    layer: CollisionLayer,
+    mask: CollisionMask,
+    bounds: CollisionBounds,
+    offscreen: DespawnWhenOffscreen,
+    hit_points: HitPoints,
+    score: ScoreValue,
+    movement: EnemyMovement,
+    interpolated: Interpolated,
     level_entity: LevelEntity,
 }
 
 impl EnemyBundle {
-    pub fn new(transform: Transform) -> Self {
+    pub fn new(transform: Transform, archetype: &EnemyArchetype) -> Self {
+        let (width, height) = archetype.size;
+        let (path, collision_shape) = match archetype.shape {
+            EnemyShape::Rectangle => (
+                GeometryBuilder::build_as(&shapes::Rectangle {
+                    extents: Vec2::new(width, height),
+                    origin: shapes::RectangleOrigin::Center,
+                }),
+                CollisionShape::new_rectangle(width, height),
+            ),
+            EnemyShape::Circle => (
+                GeometryBuilder::build_as(&shapes::Circle {
+                    radius: width / 2.0,
+                    center: Vec2::ZERO,
+                }),
+                CollisionShape::new_circle(width / 2.0),
+            ),
+            EnemyShape::Triangle => (
+                GeometryBuilder::build_as(&shapes::Polygon {
+                    points: vec![
+                        Vec2::new(-width / 2.0, height / 2.0),
+                        Vec2::new(width / 2.0, height / 2.0),
+                        Vec2::new(0.0, -height / 2.0),
+                    ],
+                    closed: true,
+                }),
+                CollisionShape::new_rectangle(width, height),
+            ),
+        };
+        let half_extents = match archetype.shape {
+            EnemyShape::Circle => Vec2::splat(width / 2.0),
+            EnemyShape::Rectangle | EnemyShape::Triangle => Vec2::new(width, height) / 2.0,
+        };
+        let (r, g, b) = archetype.color;
+
         Self {
             enemy_tag: Enemy,
             shape_bundle: ShapeBundle {
-                path: GeometryBuilder::build_as(&shapes::Rectangle {
-                    extents: Vec2::new(ENEMY_LENGTH, ENEMY_LENGTH),
-                    origin: shapes::RectangleOrigin::Center,
-                }),
+                path,
                 transform,
                 ..default()
             },
-            fill: Fill::color(Color::rgb_u8(164, 69, 55)),
-            collision_shape: CollisionShape::new_rectangle(ENEMY_LENGTH, ENEMY_LENGTH),
+            fill: Fill::color(Color::rgb_u8(r, g, b)),
+            collision_shape,
+            layer: CollisionLayer::Enemy,
+            mask: CollisionMask::new(&[CollisionLayer::Player, CollisionLayer::PlayerBullet]),
+            bounds: CollisionBounds(half_extents),
+            // once the camera has scrolled past it
+            offscreen: DespawnWhenOffscreen {
+                margin: half_extents.max_element(),
+            },
+            hit_points: HitPoints(archetype.hit_points),
+            score: ScoreValue(archetype.score),
+            movement: EnemyMovement {
+                pattern: archetype.movement.clone(),
+                origin: transform.translation.truncate(),
+                elapsed: 0.0,
+            },
+            interpolated: Interpolated::default(),
             level_entity: LevelEntity,
         }
     }
 }
 
+/// Adds everything but the `Enemy`, `EnemyKind` and `Transform` saved in a level
+pub(crate) fn build_enemy(
+    enemy: &mut EntityCommands,
+    transform: Transform,
+    archetype: &EnemyArchetype,
+) {
+    enemy.insert(EnemyBundle::new(transform, archetype));
+    if let Some(fire) = &archetype.fire {
+        enemy.insert(EnemyGun::new(fire.clone()));
+    }
+}
+
 fn after_deserialize_enemy(
     mut commands: Commands,
-    q: Query<(Entity, &Transform), (With<Enemy>, Without<CollisionShape>)>,
+    q: Query<(Entity, &Transform, Option<&EnemyKind>), (With<Enemy>, Without<CollisionShape>)>,
     mut state: ResMut<NextState<GameState>>,
+    editor: Option<Res<EditorLevel>>,
+    enemy_assets: Res<EnemyAssets>,
+    archetypes: Res<Assets<EnemyArchetypes>>,
 ) {
     if !q.is_empty() {
-        state.set(GameState::Playing);
-        for (entity, transform) in q.iter() {
-            commands.entity(entity).insert(EnemyBundle::new(*transform));
+        state.set(if editor.is_some() {
+            GameState::Editor
+        } else {
+            GameState::Playing
+        });
+        let archetypes = &archetypes.get(&enemy_assets.archetypes).unwrap().archetypes;
+        for (entity, transform, kind) in q.iter() {
+            let name = kind.map_or(DEFAULT_ARCHETYPE, |kind| kind.0.as_str());
+            if let Some(archetype) = archetypes.get(name) {
+                build_enemy(&mut commands.entity(entity), *transform, archetype);
+            } else {
+                warn!("unknown enemy archetype {name}");
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+// position along a path of points after travelling `distance` from the first point
+fn point_on_path(points: &[(f32, f32)], distance: f32, looping: bool) -> Vec2 {
+    let mut points: Vec<Vec2> = points.iter().map(|(x, y)| Vec2::new(*x, *y)).collect();
+    if points.is_empty() {
+        return Vec2::ZERO;
+    }
+    if looping {
+        points.push(points[0]);
+    }
+    let length: f32 = points.windows(2).map(|w| w[0].distance(w[1])).sum();
+    if length <= 0.0 {
+        return points[0];
+    }
+    let mut distance = if looping {
+        distance % length
+    } else {
+        distance.min(length)
+    };
+    for w in points.windows(2) {
+        let segment_length = w[0].distance(w[1]);
+        if distance <= segment_length {
+            return w[0].lerp(w[1], distance / segment_length);
         }
+        distance -= segment_length;
+    }
+    points[points.len() - 1]
+}
+
+fn move_enemies(
+    mut commands: Commands,
+    mut enemies: Query<
+        (Entity, &mut Transform, &mut EnemyMovement),
+        (With<Enemy>, Without<Velocity>),
+    >,
+    player: Query<&Transform, (With<Player>, Without<Enemy>)>,
+    fixed_time: Res<FixedTime>,
+) {
+    for (entity, mut transform, mut movement) in enemies.iter_mut() {
+        movement.elapsed += fixed_time.period.as_secs_f32();
+        let t = movement.elapsed;
+        let offset = match &movement.pattern {
+            MovementPattern::Stationary => continue,
+            MovementPattern::Sine { amplitude, period } => {
+                Vec2::new(amplitude * f32::sin(TAU * t / period), 0.0)
+            }
+            MovementPattern::Orbit { radius, period } => {
+                let angle = TAU * t / period;
+                *radius * Vec2::new(f32::cos(angle) - 1.0, f32::sin(angle))
+            }
+            MovementPattern::FollowPath {
+                points,
+                speed,
+                looping,
+            } => point_on_path(points, speed * t, *looping),
+            MovementPattern::Dive { speed, range } => {
+                // moving under its own velocity once the dive starts
+                if let Ok(player) = player.get_single() {
+                    let to_player = (player.translation - transform.translation).truncate();
+                    if to_player.y < 0.0 && -to_player.y < *range {
+                        commands
+                            .entity(entity)
+                            .insert(Velocity(to_player.normalize() * *speed));
+                    }
+                }
+                continue;
+            }
+        };
+        let z = transform.translation.z;
+        transform.translation = (movement.origin + offset).extend(z);
     }
 }
 
 fn check_collisions_with_bullets(
     mut commands: Commands,
-    bullets: Query<(Entity, &CollisionShape), (With<Bullet>, Without<Enemy>)>,
-    enemies: Query<(Entity, &CollisionShape), With<Enemy>>,
+    mut collisions: EventReader<CollisionEvent>,
+    bullets: Query<&BulletOwner, With<Bullet>>,
+    mut enemies: Query<(&mut HitPoints, &ScoreValue), With<Enemy>>,
+    mut score: ResMut<Score>,
     audio_assets: Option<Res<AudioAssets>>,
     audio: Res<Audio>,
 ) {
     if audio_assets.is_none() {
+        collisions.clear();
         return;
     }
     let audio_assets = audio_assets.unwrap();
-    for (bullet_entity, bullet_shape) in bullets.iter() {
-        let mut bullet_collided = false;
-        for (enemy_entity, enemy_shape) in enemies.iter() {
-            if bullet_shape.is_collided_with(enemy_shape) {
-                commands.entity(enemy_entity).despawn();
-                audio.play(audio_assets.explode.clone());
-                bullet_collided = true;
-            }
+    // a bullet touching several enemies only hits the first of them
+    let mut spent = HashSet::new();
+    // player bullets are on an earlier layer than enemies
+    for collision in collisions.iter() {
+        if !matches!(bullets.get(collision.a), Ok(BulletOwner::Player)) {
+            continue;
         }
-        if bullet_collided {
-            commands.entity(bullet_entity).despawn();
+        if spent.contains(&collision.a) {
+            continue;
         }
+        let (mut hit_points, value) = match enemies.get_mut(collision.b) {
+            // already destroyed by another bullet this tick
+            Ok((hit_points, _)) if hit_points.0 == 0 => continue,
+            Ok(enemy) => enemy,
+            Err(_) => continue,
+        };
+        let mut points = 0;
+        hit_points.0 -= 1;
+        if hit_points.0 == 0 {
+            commands.entity(collision.b).despawn();
+            audio.play(audio_assets.explode.clone());
+            points = value.0;
+        }
+        commands.entity(collision.a).despawn();
+        spent.insert(collision.a);
+        score.hit(points);
     }
 }
 
@@ -85,10 +336,18 @@ pub struct EnemyPlugin;
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<Enemy>()
+            .register_type::<EnemyKind>()
+            .add_asset::<EnemyArchetypes>()
+            .add_asset_loader(RonAssetLoader::<EnemyArchetypes>::new(&["enemies.ron"]))
             .add_system(
                 check_collisions_with_bullets
-                    .in_base_set(CoreSet::PostUpdate)
-                    .after(UpdateCollisionShapes),
+                    .in_set(SimulationSet::Collisions)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_system(
+                move_enemies
+                    .in_set(SimulationSet::Gameplay)
+                    .in_schedule(CoreSchedule::FixedUpdate),
             )
             .add_system(after_deserialize_enemy.in_set(OnUpdate(GameState::PostLoadLevel)));
     }
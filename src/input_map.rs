@@ -0,0 +1,309 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actions::ActiveGamepad;
+
+/// What the player can do with a button
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum InputAction {
+    Stop,
+    Shoot,
+    SteerUp,
+    SteerDown,
+    SteerLeft,
+    SteerRight,
+    /// turns the aim counterclockwise in the keyboard `AimMode`s
+    AimLeft,
+    /// turns the aim clockwise in the keyboard `AimMode`s
+    AimRight,
+}
+
+impl InputAction {
+    pub const ALL: [InputAction; 8] = [
+        InputAction::Stop,
+        InputAction::Shoot,
+        InputAction::SteerUp,
+        InputAction::SteerDown,
+        InputAction::SteerLeft,
+        InputAction::SteerRight,
+        InputAction::AimLeft,
+        InputAction::AimRight,
+    ];
+}
+
+/// How the player aims
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AimMode {
+    /// at the mouse cursor, or along the right stick
+    #[default]
+    Pointer,
+    /// `AimLeft` and `AimRight` turn the aim while held, see `InputMap::aim_rotation_speed`
+    KeyboardRotate,
+    /// `AimLeft` and `AimRight` step between 8 directions
+    KeyboardEightWay,
+}
+
+impl AimMode {
+    /// The mode after this one, for cycling through them in the controls screen
+    pub fn next(self) -> AimMode {
+        match self {
+            AimMode::Pointer => AimMode::KeyboardRotate,
+            AimMode::KeyboardRotate => AimMode::KeyboardEightWay,
+            AimMode::KeyboardEightWay => AimMode::Pointer,
+        }
+    }
+}
+
+fn default_aim_rotation_speed() -> f32 {
+    180.0
+}
+
+/// A key, mouse button or button on the active gamepad
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButtonType),
+}
+
+/// The bindings of every `InputAction`. An action happens when any of its bindings is pressed.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct InputMap {
+    pub bindings: BTreeMap<InputAction, Vec<Binding>>,
+    #[serde(default)]
+    pub aim_mode: AimMode,
+    /// degrees per second the aim turns in `AimMode::KeyboardRotate`
+    #[serde(default = "default_aim_rotation_speed")]
+    pub aim_rotation_speed: f32,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        use Binding::*;
+        Self {
+            bindings: BTreeMap::from([
+                (
+                    InputAction::Stop,
+                    vec![
+                        Key(KeyCode::F),
+                        Mouse(MouseButton::Right),
+                        Gamepad(GamepadButtonType::LeftTrigger2),
+                    ],
+                ),
+                (
+                    InputAction::Shoot,
+                    vec![
+                        Key(KeyCode::Space),
+                        Mouse(MouseButton::Left),
+                        Gamepad(GamepadButtonType::RightTrigger2),
+                    ],
+                ),
+                (
+                    InputAction::SteerUp,
+                    vec![
+                        Key(KeyCode::W),
+                        Key(KeyCode::Up),
+                        Gamepad(GamepadButtonType::DPadUp),
+                    ],
+                ),
+                (
+                    InputAction::SteerDown,
+                    vec![
+                        Key(KeyCode::S),
+                        Key(KeyCode::Down),
+                        Gamepad(GamepadButtonType::DPadDown),
+                    ],
+                ),
+                (
+                    InputAction::SteerLeft,
+                    vec![
+                        Key(KeyCode::A),
+                        Key(KeyCode::Left),
+                        Gamepad(GamepadButtonType::DPadLeft),
+                    ],
+                ),
+                (
+                    InputAction::SteerRight,
+                    vec![
+                        Key(KeyCode::D),
+                        Key(KeyCode::Right),
+                        Gamepad(GamepadButtonType::DPadRight),
+                    ],
+                ),
+                (InputAction::AimLeft, vec![Key(KeyCode::Q)]),
+                (InputAction::AimRight, vec![Key(KeyCode::E)]),
+            ]),
+            aim_mode: AimMode::default(),
+            aim_rotation_speed: default_aim_rotation_speed(),
+        }
+    }
+}
+
+impl InputMap {
+    pub fn bindings(&self, action: InputAction) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// The action `binding` is bound to, if any
+    pub fn action(&self, binding: Binding) -> Option<InputAction> {
+        self.bindings
+            .iter()
+            .find(|(_, bindings)| bindings.contains(&binding))
+            .map(|(action, _)| *action)
+    }
+
+    /// Adds `binding` to `action`. Fails with the other action if `binding` is already taken,
+    /// since one button doing two things at once is never what the player wants.
+    pub fn bind(&mut self, action: InputAction, binding: Binding) -> Result<(), InputAction> {
+        match self.action(binding) {
+            Some(other) if other != action => Err(other),
+            Some(_) => Ok(()),
+            None => {
+                self.bindings.entry(action).or_default().push(binding);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn clear(&mut self, action: InputAction) {
+        // an empty list rather than no entry, so loading doesn't bring back the defaults
+        self.bindings.insert(action, Vec::new());
+    }
+
+    // Actions added since the file was saved get their default bindings, unless they're taken.
+    fn add_missing_actions(&mut self) {
+        let defaults = InputMap::default();
+        for action in InputAction::ALL {
+            if self.bindings.contains_key(&action) {
+                continue;
+            }
+            self.bindings.insert(action, Vec::new());
+            for binding in defaults.bindings(action) {
+                let _ = self.bind(action, *binding);
+            }
+        }
+    }
+
+    pub(crate) fn pressed(&self, action: InputAction, devices: &InputDevices) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|binding| devices.pressed(*binding))
+    }
+
+    pub(crate) fn just_pressed(&self, action: InputAction, devices: &InputDevices) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|binding| devices.just_pressed(*binding))
+    }
+}
+
+/// Where the `InputMap` is saved. Defaults to the user's data directory, next to the high scores.
+#[derive(Resource)]
+pub struct InputMapFile(pub PathBuf);
+
+impl Default for InputMapFile {
+    fn default() -> Self {
+        let data_dir = dirs::data_dir().unwrap_or_else(|| PathBuf::from("."));
+        InputMapFile(data_dir.join("shoe_shmup").join("input_map.ron"))
+    }
+}
+
+/// The buttons of every input device `Binding`s can name
+#[derive(SystemParam)]
+pub(crate) struct InputDevices<'w> {
+    pub keys: Res<'w, Input<KeyCode>>,
+    pub mouse_buttons: Res<'w, Input<MouseButton>>,
+    pub gamepad_buttons: Res<'w, Input<GamepadButton>>,
+    pub gamepad: Res<'w, ActiveGamepad>,
+}
+
+impl InputDevices<'_> {
+    pub fn pressed(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.keys.pressed(key),
+            Binding::Mouse(button) => self.mouse_buttons.pressed(button),
+            Binding::Gamepad(button_type) => match self.gamepad.0 {
+                Some(gamepad) => self
+                    .gamepad_buttons
+                    .pressed(GamepadButton::new(gamepad, button_type)),
+                None => false,
+            },
+        }
+    }
+
+    pub fn just_pressed(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.keys.just_pressed(key),
+            Binding::Mouse(button) => self.mouse_buttons.just_pressed(button),
+            Binding::Gamepad(button_type) => match self.gamepad.0 {
+                Some(gamepad) => self
+                    .gamepad_buttons
+                    .just_pressed(GamepadButton::new(gamepad, button_type)),
+                None => false,
+            },
+        }
+    }
+
+    /// Any binding pressed this frame
+    pub fn any_just_pressed(&self) -> Option<Binding> {
+        let key = self
+            .keys
+            .get_just_pressed()
+            .next()
+            .copied()
+            .map(Binding::Key);
+        let mouse_button = || {
+            self.mouse_buttons
+                .get_just_pressed()
+                .next()
+                .copied()
+                .map(Binding::Mouse)
+        };
+        let gamepad_button = || {
+            self.gamepad_buttons
+                .get_just_pressed()
+                .find(|button| Some(button.gamepad) == self.gamepad.0)
+                .map(|button| Binding::Gamepad(button.button_type))
+        };
+        key.or_else(mouse_button).or_else(gamepad_button)
+    }
+}
+
+fn load_input_map(mut commands: Commands, file: Res<InputMapFile>) {
+    // no file yet means the controls were never changed
+    let mut input_map: InputMap = fs::read_to_string(&file.0)
+        .ok()
+        .and_then(|contents| match ron::from_str(&contents) {
+            Ok(input_map) => Some(input_map),
+            Err(error) => {
+                warn!("ignoring invalid input map in {:?}: {error}", file.0);
+                None
+            }
+        })
+        .unwrap_or_default();
+    input_map.add_missing_actions();
+    commands.insert_resource(input_map);
+}
+
+pub(crate) fn save_input_map(file: &InputMapFile, input_map: &InputMap) -> std::io::Result<()> {
+    if let Some(dir) = file.0.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let contents = ron::ser::to_string_pretty(input_map, default())
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+    fs::write(&file.0, contents)
+}
+
+pub struct InputMapPlugin;
+impl Plugin for InputMapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputMapFile>()
+            .init_resource::<ActiveGamepad>()
+            .add_system(load_input_map.on_startup());
+    }
+}
@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use serde::Deserialize;
+
+use crate::enemy::Enemy;
+use crate::physics::Velocity;
+use crate::ron_asset::RonAssetLoader;
+use crate::spawn::SpawnTrigger;
+use crate::{GameState, LevelEntity};
+
+/// Ordered list of the levels in the game, loaded from a `.levels.ron` file
+#[derive(Deserialize, TypeUuid, Debug)]
+#[uuid = "5b1c0b5e-3f6a-4c8e-9a44-2d1f5e7c9b31"]
+pub struct LevelManifest {
+    pub levels: Vec<LevelInfo>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LevelInfo {
+    pub name: String,
+    /// path of the scene file, relative to the assets folder
+    pub path: String,
+}
+
+/// Index into the `LevelManifest` of the level being played
+#[derive(Resource, Default)]
+pub struct CurrentLevel(pub usize);
+
+/// The level is finished when the camera scrolls past this marker
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct LevelEnd;
+
+fn after_deserialize_level_end(
+    mut commands: Commands,
+    q: Query<Entity, (With<LevelEnd>, Without<LevelEntity>)>,
+) {
+    for entity in q.iter() {
+        commands.entity(entity).insert(LevelEntity);
+    }
+}
+
+fn check_level_finished(
+    enemies: Query<(), With<Enemy>>,
+    spawn_triggers: Query<(), With<SpawnTrigger>>,
+    level_end: Query<&Transform, With<LevelEnd>>,
+    camera: Query<&Transform, (With<Camera>, With<Velocity>)>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    let camera_y = camera.single().translation.y;
+    let reached_end = level_end.iter().any(|t| camera_y >= t.translation.y);
+    let cleared = enemies.is_empty() && spawn_triggers.is_empty();
+    if cleared || reached_end {
+        state.set(GameState::LevelFinished);
+    }
+}
+
+fn reset_current_level(mut current_level: ResMut<CurrentLevel>) {
+    current_level.0 = 0;
+}
+
+fn cleanup_level(
+    q: Query<Entity, With<LevelEntity>>,
+    mut camera: Query<&mut Transform, (With<Camera>, With<Velocity>)>,
+    mut commands: Commands,
+) {
+    for e in &q {
+        commands.entity(e).despawn();
+    }
+
+    let mut transform = camera.single_mut();
+    transform.translation = Vec3::new(0.0, 0.0, transform.translation.z);
+}
+
+pub struct LevelPlugin;
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<LevelManifest>()
+            .add_asset_loader(RonAssetLoader::<LevelManifest>::new(&["levels.ron"]))
+            .init_resource::<CurrentLevel>()
+            .register_type::<LevelEnd>()
+            .add_system(after_deserialize_level_end.in_set(OnUpdate(GameState::PostLoadLevel)))
+            .add_system(check_level_finished.in_set(OnUpdate(GameState::Playing)))
+            .add_system(reset_current_level.in_schedule(OnEnter(GameState::Menu)))
+            .add_system(cleanup_level.in_schedule(OnExit(GameState::LevelFinished)))
+            .add_system(cleanup_level.in_schedule(OnExit(GameState::PlayerDead)))
+            .add_system(cleanup_level.in_schedule(OnExit(GameState::Editor)));
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use bevy::ecs::entity::EntityMap;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::constants::SCREEN_WIDTH;
+use crate::enemy::{Enemy, EnemyArchetypes, EnemyKind, DEFAULT_ARCHETYPE};
+use crate::level::LevelEnd;
+use crate::physics::FixedOffset;
+use crate::player_rail::{PlayerRail, RailError};
+use crate::scroll::ScrollTimeline;
+use crate::serialize::deserialize_scene;
+use crate::spawn::SpawnTrigger;
+
+/// Something wrong with a level file. Entities are named by their id in the file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LevelProblem {
+    /// not a scene file, or a component that doesn't match its type
+    Invalid(String),
+    UnknownType {
+        entity: u32,
+        type_name: String,
+    },
+    MissingComponent {
+        entity: u32,
+        component: &'static str,
+        needed_by: &'static str,
+    },
+    UnknownArchetype {
+        entity: u32,
+        kind: String,
+    },
+    /// levels without enemies are never started
+    NoEnemies,
+    /// partly or completely outside the playable width
+    OutsidePlayfield {
+        entity: u32,
+        x: f32,
+    },
+    Overlapping {
+        a: u32,
+        b: u32,
+    },
+    /// the player has nowhere to move
+    NoRail,
+    /// a rail needs at least two nodes to have a segment
+    RailTooShort {
+        entity: u32,
+        nodes: usize,
+    },
+    /// a branch starts or ends at a node the rail doesn't have
+    RailBranchOutOfRange {
+        entity: u32,
+        branch: usize,
+    },
+    /// keyframes have to be in order of increasing y
+    UnsortedScrollTimeline {
+        entity: u32,
+    },
+}
+
+impl fmt::Display for LevelProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelProblem::Invalid(error) => write!(f, "invalid level file: {error}"),
+            LevelProblem::UnknownType { entity, type_name } => {
+                write!(f, "entity {entity}: unknown type {type_name}")
+            }
+            LevelProblem::MissingComponent {
+                entity,
+                component,
+                needed_by,
+            } => write!(f, "entity {entity}: {needed_by} without {component}"),
+            LevelProblem::UnknownArchetype { entity, kind } => {
+                write!(f, "entity {entity}: unknown enemy archetype {kind}")
+            }
+            LevelProblem::NoEnemies => write!(f, "no enemies"),
+            LevelProblem::OutsidePlayfield { entity, x } => {
+                write!(f, "entity {entity}: enemy at x = {x} is outside the screen")
+            }
+            LevelProblem::Overlapping { a, b } => {
+                write!(f, "entities {a} and {b}: enemies overlap")
+            }
+            LevelProblem::NoRail => write!(f, "no player rail"),
+            LevelProblem::RailTooShort { entity, nodes } => {
+                write!(
+                    f,
+                    "entity {entity}: rail has {nodes} nodes, needs at least 2"
+                )
+            }
+            LevelProblem::RailBranchOutOfRange { entity, branch } => {
+                write!(
+                    f,
+                    "entity {entity}: rail branch {branch} connects a node the rail doesn't have"
+                )
+            }
+            LevelProblem::UnsortedScrollTimeline { entity } => {
+                write!(f, "entity {entity}: scroll keyframes are not sorted by y")
+            }
+        }
+    }
+}
+
+// just the component names, so every unknown type can be reported instead of only the first
+#[derive(Deserialize)]
+struct SceneFile {
+    entities: BTreeMap<u32, EntityFile>,
+}
+
+#[derive(Deserialize)]
+struct EntityFile {
+    components: BTreeMap<String, ron::Value>,
+}
+
+fn unknown_types(type_registry: &AppTypeRegistry, scene: &SceneFile) -> Vec<LevelProblem> {
+    let registry = type_registry.read();
+    scene
+        .entities
+        .iter()
+        .flat_map(|(entity, file)| file.components.keys().map(move |name| (*entity, name)))
+        .filter(|(_, type_name)| registry.get_with_name(type_name).is_none())
+        .map(|(entity, type_name)| LevelProblem::UnknownType {
+            entity,
+            type_name: type_name.clone(),
+        })
+        .collect()
+}
+
+/// Checks a level file the way the game would load it. `type_registry` has to be the game's.
+pub fn check_level(
+    type_registry: &AppTypeRegistry,
+    archetypes: &EnemyArchetypes,
+    contents: &str,
+) -> Vec<LevelProblem> {
+    let scene_file: SceneFile = match ron::from_str(contents) {
+        Ok(scene_file) => scene_file,
+        Err(error) => return vec![LevelProblem::Invalid(error.to_string())],
+    };
+    let problems = unknown_types(type_registry, &scene_file);
+    if !problems.is_empty() {
+        return problems;
+    }
+
+    let mut world = World::new();
+    world.insert_resource(type_registry.clone());
+    let mut entity_map = EntityMap::default();
+    let loaded = deserialize_scene(type_registry, contents)
+        .and_then(|scene| Ok(scene.write_to_world(&mut world, &mut entity_map)?));
+    if let Err(error) = loaded {
+        return vec![LevelProblem::Invalid(error.to_string())];
+    }
+
+    let mut problems = Vec::new();
+    let mut any_enemies = false;
+    let mut any_rail = false;
+    let mut enemies = Vec::new();
+    for &id in scene_file.entities.keys() {
+        let entity = world.entity(entity_map.get(Entity::from_raw(id)).unwrap());
+        let mut require = |has: bool, component, needed_by| {
+            if !has {
+                problems.push(LevelProblem::MissingComponent {
+                    entity: id,
+                    component,
+                    needed_by,
+                });
+            }
+        };
+        let is_enemy = entity.contains::<Enemy>();
+        let transform = entity.get::<Transform>();
+        if is_enemy {
+            require(transform.is_some(), "Transform", "Enemy");
+        }
+        if entity.contains::<EnemyKind>() {
+            require(is_enemy, "Enemy", "EnemyKind");
+        }
+        if entity.contains::<LevelEnd>() {
+            require(transform.is_some(), "Transform", "LevelEnd");
+        }
+        if let Some(rail) = entity.get::<PlayerRail>() {
+            any_rail = true;
+            require(
+                entity.contains::<FixedOffset>(),
+                "FixedOffset",
+                "PlayerRail",
+            );
+            match rail.validate() {
+                Ok(()) => {}
+                Err(RailError::TooFewNodes(nodes)) => {
+                    problems.push(LevelProblem::RailTooShort { entity: id, nodes })
+                }
+                Err(RailError::BranchOutOfRange(branch)) => {
+                    problems.push(LevelProblem::RailBranchOutOfRange { entity: id, branch })
+                }
+            }
+        }
+        if let Some(timeline) = entity.get::<ScrollTimeline>() {
+            if !timeline.keyframes.windows(2).all(|w| w[0].y < w[1].y) {
+                problems.push(LevelProblem::UnsortedScrollTimeline { entity: id });
+            }
+        }
+        if let Some(trigger) = entity.get::<SpawnTrigger>() {
+            for enemy in &trigger.wave {
+                any_enemies = true;
+                if !archetypes.archetypes.contains_key(&enemy.kind) {
+                    problems.push(LevelProblem::UnknownArchetype {
+                        entity: id,
+                        kind: enemy.kind.clone(),
+                    });
+                }
+            }
+        }
+        if !is_enemy {
+            continue;
+        }
+        any_enemies = true;
+
+        let kind = entity
+            .get::<EnemyKind>()
+            .map_or(DEFAULT_ARCHETYPE, |kind| kind.0.as_str());
+        match (archetypes.archetypes.get(kind), transform) {
+            (Some(archetype), Some(transform)) => {
+                let half_size = Vec2::new(archetype.size.0, archetype.size.1) / 2.0;
+                enemies.push((id, transform.translation.truncate(), half_size));
+            }
+            (None, _) => problems.push(LevelProblem::UnknownArchetype {
+                entity: id,
+                kind: kind.to_string(),
+            }),
+            _ => {}
+        }
+    }
+
+    if !any_enemies {
+        problems.push(LevelProblem::NoEnemies);
+    }
+    if !any_rail {
+        problems.push(LevelProblem::NoRail);
+    }
+    for (i, &(a, center_a, half_a)) in enemies.iter().enumerate() {
+        if center_a.x.abs() + half_a.x > SCREEN_WIDTH / 2.0 {
+            problems.push(LevelProblem::OutsidePlayfield {
+                entity: a,
+                x: center_a.x,
+            });
+        }
+        for &(b, center_b, half_b) in &enemies[i + 1..] {
+            if (center_a - center_b).abs().cmplt(half_a + half_b).all() {
+                problems.push(LevelProblem::Overlapping { a, b });
+            }
+        }
+    }
+    problems
+}
@@ -3,39 +3,95 @@
 
 mod actions;
 mod audio;
+mod broad_phase;
 mod bullet;
+mod bullet_pattern;
+mod checkpoint;
 mod constants;
+mod controls_menu;
+mod editor;
 mod enemy;
+mod input_map;
+mod level;
+mod level_check;
+mod lives;
 mod loading;
 mod menu;
 mod pause_menu;
 mod physics;
 mod player;
 mod player_rail;
+mod playfield;
+mod replay;
+mod results_menu;
+mod ron_asset;
+mod score;
+mod scroll;
 mod serialize;
+mod spawn;
 
-use crate::actions::ActionsPlugin;
-use crate::audio::InternalAudioPlugin;
+use crate::actions::HeadlessActionsPlugin;
+use crate::audio::{HeadlessAudioPlugin, InternalAudioPlugin};
+use crate::broad_phase::BroadPhasePlugin;
 use crate::bullet::BulletPlugin;
+use crate::bullet_pattern::BulletPatternPlugin;
+use crate::checkpoint::CheckpointPlugin;
 pub use crate::constants::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::controls_menu::ControlsMenuPlugin;
+use crate::editor::EditorPlugin;
 use crate::enemy::EnemyPlugin;
-use crate::loading::LoadingPlugin;
+use crate::input_map::InputMapPlugin;
+use crate::level::LevelPlugin;
+use crate::lives::LivesPlugin;
+use crate::loading::{HeadlessLoadingPlugin, LoadingPlugin};
 use crate::menu::MenuPlugin;
 use crate::pause_menu::PauseMenuPlugin;
 use crate::physics::PhysicsPlugin;
 use crate::player::PlayerPlugin;
+use crate::replay::ReplayPlugin;
+use crate::results_menu::ResultsMenuPlugin;
+use crate::score::ScorePlugin;
+use crate::scroll::ScrollPlugin;
 use crate::serialize::SerializePlugin;
+use crate::spawn::SpawnPlugin;
+
+pub use crate::actions::{Actions, ActionsPlugin, ActionsSet, ActiveGamepad};
+pub use crate::broad_phase::BroadPhase;
+pub use crate::bullet::{Bullet, BulletOwner, SpawnBullet};
+pub use crate::bullet_pattern::Emitter;
+pub use crate::checkpoint::Checkpoint;
+pub use crate::editor::{ChosenLevel, EditorCursor, EditorLevel};
+pub use crate::enemy::{Enemy, EnemyArchetypes, EnemyKind, HitPoints, ScoreValue};
+pub use crate::input_map::{AimMode, Binding, InputAction, InputMap, InputMapFile};
+pub use crate::level::{CurrentLevel, LevelEnd};
+pub use crate::level_check::{check_level, LevelProblem};
+pub use crate::lives::{Invulnerable, Lives, INVULNERABLE_SECONDS, STARTING_LIVES};
+pub use crate::physics::{
+    sweep, CollisionBounds, CollisionEvent, CollisionLayer, CollisionMask, DespawnWhenOffscreen,
+    LeftScreen, Lifetime, Swept,
+};
+pub use crate::player::Player;
+pub use crate::player_rail::{PlayerRail, RailBranch, RailDirection, RailError, RailPosition};
+pub use crate::playfield::PlayfieldPlugin;
+pub use crate::replay::{Replay, ReplayEnd, ReplayFrame, ReplayMode, ReplayResult};
+pub use crate::score::{HighScoreFile, HighScores, Score};
+pub use crate::scroll::{ScrollKeyframe, ScrollPause, ScrollTimeline};
+pub use crate::serialize::{
+    migrate_level, SaveSceneCompleted, SaveSceneEvent, SaveSceneFailed, SceneFilter,
+};
+pub use crate::spawn::{SpawnCondition, SpawnTrigger, WaveEnemy};
 
 #[cfg(debug_assertions)]
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
 use bevy::prelude::*;
 use bevy::{app::App, diagnostic::EntityCountDiagnosticsPlugin};
+use bevy_prototype_lyon::prelude::ShapePlugin;
 
 // This example game uses States to separate logic
 // See https://bevy-cheatbook.github.io/programming/states.html
 // Or https://github.com/bevyengine/bevy/blob/main/examples/ecs/state.rs
 #[derive(States, Clone, Eq, PartialEq, Debug, Hash, Default)]
-enum GameState {
+pub enum GameState {
     // During the loading State the LoadingPlugin will load our assets
     #[default]
     LoadingAssets,
@@ -50,7 +106,12 @@ enum GameState {
     // Here the menu is drawn and waiting for player interaction
     Menu,
     Paused,
+    // out of `Lives`, the run is over unless it continues from a `Checkpoint`
     PlayerDead,
+    // placing enemies in a level, see `EditorLevel`
+    Editor,
+    // rebinding the `InputMap`
+    Controls,
 }
 
 #[derive(Component)]
@@ -60,17 +121,12 @@ pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        app.add_state::<GameState>()
+        app.add_plugin(GameLogicPlugin)
             .add_plugin(LoadingPlugin)
-            .add_plugin(MenuPlugin)
             .add_plugin(ActionsPlugin)
+            .add_plugin(PlayfieldPlugin)
             .add_plugin(InternalAudioPlugin)
-            .add_plugin(PlayerPlugin)
-            .add_plugin(PhysicsPlugin)
-            .add_plugin(BulletPlugin)
-            .add_plugin(EnemyPlugin)
-            .add_plugin(PauseMenuPlugin)
-            .add_plugin(SerializePlugin);
+            .add_plugin(ShapePlugin);
 
         #[cfg(debug_assertions)]
         {
@@ -80,3 +136,47 @@ impl Plugin for GamePlugin {
         }
     }
 }
+
+/// Runs the game without a window, GPU or audio device.
+/// Assets, audio and shape rendering are replaced with no-op stand-ins and nothing reads
+/// input devices, so `Actions` and `GameState` have to be driven by the app owner.
+/// Meant to be added on top of `DefaultPlugins` with the renderer backends and winit disabled.
+pub struct HeadlessGamePlugin;
+
+impl Plugin for HeadlessGamePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(GameLogicPlugin)
+            .add_plugin(HeadlessLoadingPlugin)
+            .add_plugin(HeadlessActionsPlugin)
+            .add_plugin(HeadlessAudioPlugin);
+    }
+}
+
+// player, enemy, bullet, physics and serialization logic shared by the windowed and headless games
+struct GameLogicPlugin;
+
+impl Plugin for GameLogicPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<GameState>()
+            .add_plugin(MenuPlugin)
+            .add_plugin(InputMapPlugin)
+            .add_plugin(ControlsMenuPlugin)
+            .add_plugin(PlayerPlugin)
+            .add_plugin(LivesPlugin)
+            .add_plugin(PhysicsPlugin)
+            .add_plugin(BroadPhasePlugin)
+            .add_plugin(BulletPlugin)
+            .add_plugin(BulletPatternPlugin)
+            .add_plugin(EnemyPlugin)
+            .add_plugin(PauseMenuPlugin)
+            .add_plugin(LevelPlugin)
+            .add_plugin(CheckpointPlugin)
+            .add_plugin(ScrollPlugin)
+            .add_plugin(SpawnPlugin)
+            .add_plugin(ResultsMenuPlugin)
+            .add_plugin(ScorePlugin)
+            .add_plugin(ReplayPlugin)
+            .add_plugin(EditorPlugin)
+            .add_plugin(SerializePlugin);
+    }
+}
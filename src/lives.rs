@@ -0,0 +1,110 @@
+use bevy::prelude::*;
+
+use crate::loading::FontAssets;
+use crate::physics::{FixedOffset, SimulationSet};
+use crate::{GameState, LevelEntity};
+
+/// Lives the player starts a run with
+pub const STARTING_LIVES: u32 = 3;
+/// Seconds the player can't be hit after respawning
+pub const INVULNERABLE_SECONDS: f32 = 2.0;
+// seconds the player is shown or hidden while blinking
+const BLINK_SECONDS: f32 = 0.1;
+
+/// Lives left in the current run, including the one being played.
+/// Carried over between levels and reset when returning to the menu.
+#[derive(Resource, Debug, PartialEq, Eq)]
+pub struct Lives(pub u32);
+
+impl Default for Lives {
+    fn default() -> Self {
+        Lives(STARTING_LIVES)
+    }
+}
+
+/// Collisions with the player are ignored until the timer finishes. The player blinks meanwhile.
+#[derive(Component)]
+pub struct Invulnerable(pub Timer);
+
+impl Default for Invulnerable {
+    fn default() -> Self {
+        Invulnerable(Timer::from_seconds(INVULNERABLE_SECONDS, TimerMode::Once))
+    }
+}
+
+#[derive(Component)]
+struct LivesText;
+
+fn reset_lives(mut lives: ResMut<Lives>) {
+    *lives = Lives::default();
+}
+
+// ticked with the simulation, so replays agree on when the player can be hit again
+fn tick_invulnerability(
+    mut commands: Commands,
+    mut q: Query<(Entity, &mut Invulnerable, &mut Visibility)>,
+    fixed_time: Res<FixedTime>,
+) {
+    for (entity, mut invulnerable, mut visibility) in q.iter_mut() {
+        invulnerable.0.tick(fixed_time.period);
+        if invulnerable.0.finished() {
+            commands.entity(entity).remove::<Invulnerable>();
+            *visibility = Visibility::Inherited;
+            continue;
+        }
+        let blink = (invulnerable.0.elapsed_secs() / BLINK_SECONDS) % 2.0;
+        *visibility = if blink < 1.0 {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+    }
+}
+
+fn lives_text(lives: &Lives) -> String {
+    format!("lives {}", lives.0)
+}
+
+// shows the lives left on the other side of the bullet clip from the score
+fn spawn_lives_text(mut commands: Commands, font_assets: Res<FontAssets>, lives: Res<Lives>) {
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                lives_text(&lives),
+                TextStyle {
+                    font: font_assets.fira_sans.clone(),
+                    font_size: 20.0,
+                    color: Color::rgb(0.0, 0.0, 0.0),
+                },
+            ),
+            ..default()
+        },
+        FixedOffset(Vec2::new(-80.0, -234.0)),
+        LivesText,
+        LevelEntity,
+    ));
+}
+
+fn update_lives_text(lives: Res<Lives>, mut text: Query<&mut Text, With<LivesText>>) {
+    if !lives.is_changed() {
+        return;
+    }
+    for mut text in text.iter_mut() {
+        text.sections[0].value = lives_text(&lives);
+    }
+}
+
+pub struct LivesPlugin;
+impl Plugin for LivesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Lives>()
+            .add_system(reset_lives.in_schedule(OnEnter(GameState::Menu)))
+            .add_system(spawn_lives_text.in_schedule(OnEnter(GameState::LoadLevel)))
+            .add_system(update_lives_text.in_set(OnUpdate(GameState::Playing)))
+            .add_system(
+                tick_invulnerability
+                    .in_set(SimulationSet::Gameplay)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            );
+    }
+}
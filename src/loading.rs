@@ -1,4 +1,7 @@
+use crate::enemy::EnemyArchetypes;
+use crate::level::LevelManifest;
 use crate::GameState;
+use bevy::asset::LoadState;
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
 use bevy_kira_audio::AudioSource;
@@ -15,10 +18,57 @@ impl Plugin for LoadingPlugin {
         )
         .add_collection_to_loading_state::<_, FontAssets>(GameState::LoadingAssets)
         .add_collection_to_loading_state::<_, AudioAssets>(GameState::LoadingAssets)
+        .add_collection_to_loading_state::<_, LevelAssets>(GameState::LoadingAssets)
+        .add_collection_to_loading_state::<_, EnemyAssets>(GameState::LoadingAssets)
         .add_collection_to_loading_state::<_, TextureAssets>(GameState::LoadingAssets);
     }
 }
 
+/// Skips asset loading and goes straight to the menu once the level and enemy data is loaded.
+/// The other asset collections are filled with default handles, so nothing can be drawn or played with them.
+pub struct HeadlessLoadingPlugin;
+
+impl Plugin for HeadlessLoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(FontAssets {
+            fira_sans: Handle::default(),
+        })
+        .insert_resource(AudioAssets {
+            shoot: Handle::default(),
+            empty_clip: Handle::default(),
+            reload: Handle::default(),
+            explode: Handle::default(),
+            player_death: Handle::default(),
+        })
+        .insert_resource(TextureAssets {
+            texture_bevy: Handle::default(),
+        })
+        .add_system(load_data_assets.on_startup())
+        .add_system(skip_loading.in_set(OnUpdate(GameState::LoadingAssets)));
+    }
+}
+
+fn load_data_assets(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(LevelAssets {
+        manifest: asset_server.load("levels/manifest.levels.ron"),
+    });
+    commands.insert_resource(EnemyAssets {
+        archetypes: asset_server.load("enemies/archetypes.enemies.ron"),
+    });
+}
+
+fn skip_loading(
+    mut state: ResMut<NextState<GameState>>,
+    asset_server: Res<AssetServer>,
+    level_assets: Res<LevelAssets>,
+    enemy_assets: Res<EnemyAssets>,
+) {
+    let handles = [level_assets.manifest.id(), enemy_assets.archetypes.id()];
+    if asset_server.get_group_load_state(handles) == LoadState::Loaded {
+        state.set(GameState::Menu);
+    }
+}
+
 // the following asset collections will be loaded during the State `GameState::Loading`
 // when done loading, they will be inserted as resources (see https://github.com/NiklasEi/bevy_asset_loader)
 
@@ -42,6 +92,18 @@ pub struct AudioAssets {
     pub player_death: Handle<AudioSource>,
 }
 
+#[derive(Resource, AssetCollection)]
+pub struct LevelAssets {
+    #[asset(path = "levels/manifest.levels.ron")]
+    pub manifest: Handle<LevelManifest>,
+}
+
+#[derive(Resource, AssetCollection)]
+pub struct EnemyAssets {
+    #[asset(path = "enemies/archetypes.enemies.ron")]
+    pub archetypes: Handle<EnemyArchetypes>,
+}
+
 #[derive(Resource, AssetCollection)]
 pub struct TextureAssets {
     #[asset(path = "textures/bevy.png")]
@@ -6,11 +6,41 @@
 use bevy::prelude::{App, ClearColor, Color, Msaa, PluginGroup, Window};
 use bevy::window::{WindowPlugin, WindowResolution};
 use bevy::DefaultPlugins;
-use shoe_shmup::{GamePlugin, SCREEN_HEIGHT, SCREEN_WIDTH};
+use shoe_shmup::{EditorLevel, GamePlugin, Replay, ReplayMode, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+// `--record <file>` records levels as they are played,
+// `--replay <file>` plays a recording back and `--verify <file>` also checks it ends the same way
+fn replay_mode() -> Option<ReplayMode> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.as_slice() {
+        [flag, path] if flag == "--record" => Some(ReplayMode::record(path, None)),
+        [flag, path] if flag == "--replay" || flag == "--verify" => {
+            let replay = Replay::load(path)
+                .unwrap_or_else(|error| panic!("failed to load replay {path}: {error}"));
+            Some(ReplayMode::playback(replay, flag == "--verify"))
+        }
+        _ => None,
+    }
+}
+
+// `--edit <file>` opens a level file, relative to the assets folder, in the level editor
+fn editor_level() -> Option<EditorLevel> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.as_slice() {
+        [flag, path] if flag == "--edit" => Some(EditorLevel::new(path)),
+        _ => None,
+    }
+}
 
 fn main() {
-    App::new()
-        .insert_resource(Msaa::Sample4)
+    let mut app = App::new();
+    if let Some(mode) = replay_mode() {
+        app.insert_resource(mode);
+    }
+    if let Some(editor) = editor_level() {
+        app.insert_resource(editor);
+    }
+    app.insert_resource(Msaa::Sample4)
         .insert_resource(ClearColor(Color::rgb_u8(211, 228, 222)))
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
@@ -1,5 +1,7 @@
 use crate::loading::FontAssets;
 use crate::physics::Velocity;
+use crate::playfield::playfield_projection;
+use crate::scroll::DEFAULT_SCROLL_SPEED;
 use crate::GameState;
 use bevy::prelude::*;
 
@@ -12,7 +14,8 @@ impl Plugin for MenuPlugin {
         app.init_resource::<ButtonColors>()
             .add_system(setup_camera.on_startup())
             .add_system(setup_menu.in_schedule(OnEnter(GameState::Menu)))
-            .add_system(click_play_button.in_set(OnUpdate(GameState::Menu)));
+            .add_system(click_play_button.in_set(OnUpdate(GameState::Menu)))
+            .add_system(despawn_menu.in_schedule(OnExit(GameState::Menu)));
     }
 }
 
@@ -34,8 +37,19 @@ impl Default for ButtonColors {
 #[derive(Component)]
 struct PlayButton;
 
+#[derive(Component)]
+struct MenuHint;
+
 fn setup_camera(mut commands: Commands) {
-    commands.spawn((Camera2dBundle::default(), Velocity(Vec2::new(0.0, 20.0))));
+    commands.spawn((
+        Camera2dBundle {
+            projection: playfield_projection(),
+            ..default()
+        },
+        // the UI covers the whole window, see `PlayfieldPlugin`
+        UiCameraConfig { show_ui: false },
+        Velocity(Vec2::new(0.0, DEFAULT_SCROLL_SPEED)),
+    ));
 }
 
 fn setup_menu(
@@ -75,6 +89,29 @@ fn setup_menu(
                 ..Default::default()
             });
         });
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section(
+                "E: level editor, C: controls",
+                TextStyle {
+                    font: font_assets.fira_sans.clone(),
+                    font_size: 20.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(5.0),
+                    bottom: Val::Px(5.0),
+                    ..default()
+                },
+                ..default()
+            },
+            ..default()
+        },
+        MenuHint,
+    ));
 }
 
 pub type ButtonInteraction<'a> = (
@@ -85,18 +122,13 @@ pub type ButtonInteraction<'a> = (
 );
 
 fn click_play_button(
-    mut commands: Commands,
     button_colors: Res<ButtonColors>,
     mut state: ResMut<NextState<GameState>>,
-    mut interaction_query: Query<ButtonInteraction, (Changed<Interaction>, With<Button>)>,
-    text_query: Query<Entity, With<Text>>,
+    mut interaction_query: Query<ButtonInteraction, (Changed<Interaction>, With<PlayButton>)>,
 ) {
-    for (button, interaction, mut color, children) in interaction_query.iter_mut() {
-        let text = text_query.get(children[0]).unwrap();
+    for (_button, interaction, mut color, _children) in interaction_query.iter_mut() {
         match *interaction {
             Interaction::Clicked => {
-                commands.entity(button).despawn();
-                commands.entity(text).despawn();
                 state.set(GameState::LoadLevel);
             }
             Interaction::Hovered => {
@@ -108,3 +140,9 @@ fn click_play_button(
         }
     }
 }
+
+fn despawn_menu(mut commands: Commands, q: Query<Entity, Or<(With<PlayButton>, With<MenuHint>)>>) {
+    for e in q.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
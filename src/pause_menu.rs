@@ -3,9 +3,12 @@ use bevy::prelude::*;
 
 use crate::loading::FontAssets;
 use crate::menu::{ButtonColors, ButtonInteraction};
-use crate::serialize::SaveSceneEvent;
+use crate::serialize::{SaveSceneCompleted, SaveSceneEvent, SaveSceneFailed};
 use crate::GameState;
 
+// where the save button writes the level being played
+const SAVE_PATH: &str = "assets/levels/temp.ron";
+
 #[derive(Component)]
 struct PauseMenu;
 
@@ -15,6 +18,8 @@ struct ExitButton;
 struct CloseButton;
 #[derive(Component)]
 struct SaveButton;
+#[derive(Component)]
+struct SaveStatus;
 
 fn setup_pause_menu(
     mut commands: Commands,
@@ -114,6 +119,20 @@ fn setup_pause_menu(
                     PauseMenu,
                 ));
             });
+
+        // filled in once a save finishes
+        parent.spawn((
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::rgb(0.15, 0.15, 0.15),
+                    ..text_style
+                },
+            ),
+            SaveStatus,
+            PauseMenu,
+        ));
     });
 }
 
@@ -174,7 +193,31 @@ fn click_save_button(
 ) {
     for (_button, interaction, mut _color, _children) in interaction_query.iter_mut() {
         if *interaction == Interaction::Clicked {
-            save_scene.send(SaveSceneEvent);
+            save_scene.send(SaveSceneEvent {
+                path: SAVE_PATH.into(),
+            });
+        }
+    }
+}
+
+fn show_save_result(
+    mut completed: EventReader<SaveSceneCompleted>,
+    mut failed: EventReader<SaveSceneFailed>,
+    mut status: Query<&mut Text, With<SaveStatus>>,
+) {
+    let completed = completed
+        .iter()
+        .map(|completed| format!("Saved to {}", completed.path.display()));
+    let failed = failed.iter().map(|failed| {
+        format!(
+            "Saving to {} failed: {}",
+            failed.path.display(),
+            failed.error
+        )
+    });
+    if let Some(message) = completed.chain(failed).last() {
+        for mut text in status.iter_mut() {
+            text.sections[0].value = message.clone();
         }
     }
 }
@@ -189,6 +232,7 @@ impl Plugin for PauseMenuPlugin {
                     hover_button,
                     click_close_button,
                     click_save_button,
+                    show_save_result,
                 )
                     .in_set(OnUpdate(GameState::Paused)),
             )
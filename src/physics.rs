@@ -1,31 +1,248 @@
+use std::collections::BTreeMap;
+
 use bevy::prelude::*;
 use bevy::transform::TransformSystem;
 use impacted::CollisionShape;
 
+use crate::broad_phase::BroadPhase;
+use crate::constants::{SCREEN_HEIGHT, SCREEN_WIDTH};
 use crate::GameState;
 
+/// Order of the gameplay simulation in `CoreSchedule::FixedUpdate`.
+/// Everything in these sets only runs while `GameState::Playing`.
+/// The tick rate is set with bevy's `FixedTime` resource.
+#[derive(SystemSet, Hash, PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SimulationSet {
+    /// moving entities with a `Velocity`, keeping `FixedOffset`s in place and despawning
+    /// entities that left the screen or ran out of `Lifetime`
+    Physics,
+    /// player and enemy behaviour
+    Gameplay,
+    /// reacting to `CollisionEvent`s, after `UpdateCollisionShapes` and `DetectCollisions`
+    Collisions,
+}
+
 #[derive(SystemSet, Hash, PartialEq, Eq, Debug, Clone)]
 pub struct UpdateCollisionShapes;
 
+/// Sends a `CollisionEvent` for every pair of touching shapes whose layers interact
+#[derive(SystemSet, Hash, PartialEq, Eq, Debug, Clone)]
+pub struct DetectCollisions;
+
+/// What kind of thing a collision shape belongs to. Collision events name the entity on the
+/// earlier layer first.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CollisionLayer {
+    Player,
+    PlayerBullet,
+    Enemy,
+    EnemyBullet,
+    Pickup,
+    Wall,
+}
+
+impl CollisionLayer {
+    pub const ALL: [CollisionLayer; 6] = [
+        CollisionLayer::Player,
+        CollisionLayer::PlayerBullet,
+        CollisionLayer::Enemy,
+        CollisionLayer::EnemyBullet,
+        CollisionLayer::Pickup,
+        CollisionLayer::Wall,
+    ];
+
+    fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+/// Layers an entity's shape collides with. Two shapes collide when either one's mask contains the
+/// other's layer.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CollisionMask(u32);
+
+impl CollisionMask {
+    pub fn new(layers: &[CollisionLayer]) -> Self {
+        CollisionMask(layers.iter().fold(0, |mask, layer| mask | layer.bit()))
+    }
+
+    pub fn contains(self, layer: CollisionLayer) -> bool {
+        self.0 & layer.bit() != 0
+    }
+}
+
+/// Half the width and height of the box around an entity's `CollisionShape`
+#[derive(Component, Clone, Copy, Debug)]
+pub struct CollisionBounds(pub Vec2);
+
+/// Checks the shape along its `Velocity` over the whole tick instead of only where it ends up,
+/// so it can't pass through thin shapes. Only the first shape along its path is hit.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Swept;
+
+/// Two shapes touched this tick. `a` is on the earlier `CollisionLayer`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollisionEvent {
+    pub a: Entity,
+    pub b: Entity,
+}
+
 #[derive(Component)]
 pub struct Velocity(pub Vec2);
 
-fn update_position(mut query: Query<(&mut Transform, &Velocity)>, time: Res<Time>) {
+/// Despawns the entity once it is more than `margin` outside the screen, sending `LeftScreen`.
+/// Entities that start off screen, like enemies the camera hasn't reached yet,
+/// are left alone until they have been on screen.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct DespawnWhenOffscreen {
+    pub margin: f32,
+}
+
+// the entity has been on screen, so it can leave it
+#[derive(Component)]
+struct SeenOnScreen;
+
+/// A `DespawnWhenOffscreen` entity left the screen and is despawned at the end of this tick
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LeftScreen(pub Entity);
+
+/// Despawns the entity when the timer finishes. Ticks with the simulation.
+#[derive(Component, Clone, Debug)]
+pub struct Lifetime(pub Timer);
+
+impl Lifetime {
+    pub fn from_seconds(seconds: f32) -> Self {
+        Self(Timer::from_seconds(seconds, TimerMode::Once))
+    }
+}
+
+/// Draws an entity moved by the fixed timestep simulation between its last two simulated positions,
+/// so movement looks smooth when the frame rate doesn't match the tick rate.
+/// Entities with a `Velocity` or `FixedOffset` get this automatically.
+#[derive(Component, Default)]
+pub struct Interpolated {
+    previous: Vec3,
+    current: Vec3,
+}
+
+fn update_position(mut query: Query<(&mut Transform, &Velocity)>, fixed_time: Res<FixedTime>) {
     for (mut pos, vel) in query.iter_mut() {
-        pos.translation += vel.0.extend(0.0) * time.delta_seconds();
+        pos.translation += vel.0.extend(0.0) * fixed_time.period.as_secs_f32();
     }
 }
 
-fn update_shape_transforms(
-    mut shapes: Query<(&mut CollisionShape, &GlobalTransform), Changed<GlobalTransform>>,
-) {
+// shapes follow the simulated `Transform`, the `GlobalTransform` might be interpolated
+fn update_shape_transforms(mut shapes: Query<(&mut CollisionShape, &Transform)>) {
     for (mut shape, transform) in shapes.iter_mut() {
-        shape.set_transform(*transform);
+        shape.set_transform(GlobalTransform::from(*transform));
+    }
+}
+
+/// Continuous collision check for a shape that moved with `velocity` for `duration` to end at
+/// `transform`, so fast shapes can't pass through thin ones between ticks.
+/// The shape is moved along its path in steps no longer than `step`, which should be at most
+/// the size of the shape. Returns the first of `targets` touched and the fraction of `duration`
+/// at which it was touched.
+pub fn sweep<'a, T>(
+    shape: &CollisionShape,
+    transform: &Transform,
+    velocity: Vec2,
+    duration: f32,
+    step: f32,
+    targets: impl Iterator<Item = (T, &'a CollisionShape)>,
+) -> Option<(T, f32)> {
+    let mut targets: Vec<_> = targets.collect();
+    let path = velocity * duration;
+    let steps = (path.length() / step).ceil().max(1.0) as u32;
+    for i in 0..=steps {
+        let fraction = i as f32 / steps as f32;
+        let translation = transform.translation - (path * (1.0 - fraction)).extend(0.0);
+        let swept = shape
+            .clone()
+            .with_transform(GlobalTransform::from(Transform {
+                translation,
+                ..*transform
+            }));
+        if let Some(index) = targets
+            .iter()
+            .position(|(_, target)| swept.is_collided_with(target))
+        {
+            return Some((targets.swap_remove(index).0, fraction));
+        }
+    }
+    None
+}
+
+// orders a pair of colliding entities by layer
+fn collision_event(a: (Entity, CollisionLayer), b: (Entity, CollisionLayer)) -> CollisionEvent {
+    let ((a, _), (b, _)) = if a.1 <= b.1 { (a, b) } else { (b, a) };
+    CollisionEvent { a, b }
+}
+
+fn detect_collisions(
+    broad_phase: Res<BroadPhase>,
+    shapes: Query<(
+        &CollisionShape,
+        &CollisionLayer,
+        &CollisionMask,
+        &CollisionBounds,
+        &Transform,
+        Option<&Velocity>,
+        Option<&Swept>,
+    )>,
+    fixed_time: Res<FixedTime>,
+    mut collisions: EventWriter<CollisionEvent>,
+) {
+    // candidates of each swept shape, by entity so they are swept in a repeatable order
+    let mut swept_candidates: BTreeMap<Entity, Vec<Entity>> = BTreeMap::new();
+    for (i, &layer_a) in CollisionLayer::ALL.iter().enumerate() {
+        for &layer_b in &CollisionLayer::ALL[i..] {
+            for (a, b) in broad_phase.candidate_pairs(layer_a, layer_b) {
+                let (
+                    (shape_a, _, mask_a, _, _, _, swept_a),
+                    (shape_b, _, mask_b, _, _, _, swept_b),
+                ) = match (shapes.get(a), shapes.get(b)) {
+                    (Ok(shape_a), Ok(shape_b)) => (shape_a, shape_b),
+                    // shapes without a mask don't collide
+                    _ => continue,
+                };
+                if !mask_a.contains(layer_b) && !mask_b.contains(layer_a) {
+                    continue;
+                }
+                if swept_a.is_some() {
+                    swept_candidates.entry(a).or_default().push(b);
+                } else if swept_b.is_some() {
+                    swept_candidates.entry(b).or_default().push(a);
+                } else if shape_a.is_collided_with(shape_b) {
+                    collisions.send(CollisionEvent { a, b });
+                }
+            }
+        }
+    }
+
+    for (entity, candidates) in swept_candidates {
+        let (shape, &layer, _, bounds, transform, velocity, _) = shapes.get(entity).unwrap();
+        let velocity = velocity.map_or(Vec2::ZERO, |velocity| velocity.0);
+        let hit = sweep(
+            shape,
+            transform,
+            velocity,
+            fixed_time.period.as_secs_f32(),
+            bounds.0.min_element(),
+            candidates
+                .into_iter()
+                .map(|other| (other, shapes.get(other).unwrap()))
+                .map(|(other, (shape, &layer, ..))| ((other, layer), shape)),
+        );
+        if let Some((other, _)) = hit {
+            collisions.send(collision_event((entity, layer), other));
+        }
     }
 }
 
 // Marks entity as fixed in relation to the camera
-#[derive(Component)]
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
 pub struct FixedOffset(pub Vec2);
 
 fn update_fixed_position(
@@ -41,17 +258,155 @@ fn update_fixed_position(
     }
 }
 
+fn despawn_offscreen(
+    mut commands: Commands,
+    entities: Query<(
+        Entity,
+        &Transform,
+        &DespawnWhenOffscreen,
+        Option<&SeenOnScreen>,
+    )>,
+    camera: Query<&Transform, (With<Camera>, With<Velocity>)>,
+    mut left_screen: EventWriter<LeftScreen>,
+) {
+    let camera = camera.single().translation.truncate();
+    let half_screen = Vec2::new(SCREEN_WIDTH, SCREEN_HEIGHT) / 2.0;
+    for (entity, transform, offscreen, seen) in entities.iter() {
+        let distance = (transform.translation.truncate() - camera).abs() - half_screen;
+        let on_screen = distance.max_element() <= 0.0;
+        if seen.is_none() {
+            if on_screen {
+                commands.entity(entity).insert(SeenOnScreen);
+            }
+        } else if distance.max_element() > offscreen.margin {
+            commands.entity(entity).despawn();
+            left_screen.send(LeftScreen(entity));
+        }
+    }
+}
+
+fn despawn_expired(
+    mut commands: Commands,
+    mut lifetimes: Query<(Entity, &mut Lifetime)>,
+    fixed_time: Res<FixedTime>,
+) {
+    for (entity, mut lifetime) in lifetimes.iter_mut() {
+        if lifetime.0.tick(fixed_time.period).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+// runs first in the tick so collisions later in the tick can still despawn new entities
+fn start_interpolation(
+    mut commands: Commands,
+    new_entities: Query<
+        Entity,
+        (
+            Or<(With<Velocity>, With<FixedOffset>)>,
+            Without<Interpolated>,
+        ),
+    >,
+    mut interpolated: Query<(&Transform, &mut Interpolated)>,
+) {
+    for entity in new_entities.iter() {
+        commands.entity(entity).insert(Interpolated::default());
+    }
+    for (transform, mut interpolated) in interpolated.iter_mut() {
+        interpolated.previous = transform.translation;
+    }
+}
+
+fn end_interpolation(mut interpolated: Query<(&Transform, &mut Interpolated)>) {
+    for (transform, mut interpolated) in interpolated.iter_mut() {
+        interpolated.current = transform.translation;
+    }
+}
+
+fn interpolate_transforms(
+    fixed_time: Res<FixedTime>,
+    mut interpolated: Query<(&Transform, &mut GlobalTransform, &Interpolated), Without<Parent>>,
+) {
+    let alpha = fixed_time.accumulated().as_secs_f32() / fixed_time.period.as_secs_f32();
+    for (transform, mut global_transform, interpolated) in interpolated.iter_mut() {
+        // moved outside of the simulation, e.g. reset between levels
+        if transform.translation != interpolated.current {
+            continue;
+        }
+        let translation = interpolated.previous.lerp(interpolated.current, alpha);
+        *global_transform = Transform {
+            translation,
+            ..*transform
+        }
+        .into();
+    }
+}
+
 pub struct PhysicsPlugin;
 impl Plugin for PhysicsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            (update_position, update_fixed_position).in_set(OnUpdate(GameState::Playing)),
-        )
-        .add_system(
-            update_shape_transforms
-                .in_base_set(CoreSet::PostUpdate)
-                .in_set(UpdateCollisionShapes)
-                .after(TransformSystem::TransformPropagate),
-        );
+        app.register_type::<FixedOffset>()
+            .edit_schedule(CoreSchedule::FixedUpdate, |schedule| {
+                schedule.configure_sets(
+                    (
+                        SimulationSet::Physics,
+                        SimulationSet::Gameplay,
+                        UpdateCollisionShapes,
+                        DetectCollisions,
+                        SimulationSet::Collisions,
+                    )
+                        .chain(),
+                );
+                for set in [
+                    SimulationSet::Physics,
+                    SimulationSet::Gameplay,
+                    SimulationSet::Collisions,
+                ] {
+                    schedule.configure_set(set.run_if(in_state(GameState::Playing)));
+                }
+                schedule.configure_set(UpdateCollisionShapes.run_if(in_state(GameState::Playing)));
+                schedule.configure_set(DetectCollisions.run_if(in_state(GameState::Playing)));
+            })
+            .add_event::<CollisionEvent>()
+            .add_event::<LeftScreen>()
+            .add_systems(
+                (
+                    update_position,
+                    update_fixed_position,
+                    despawn_offscreen,
+                    despawn_expired,
+                )
+                    .chain()
+                    .in_set(SimulationSet::Physics)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_system(
+                update_shape_transforms
+                    .in_set(UpdateCollisionShapes)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_system(
+                detect_collisions
+                    .in_set(DetectCollisions)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_system(
+                start_interpolation
+                    .before(SimulationSet::Physics)
+                    .run_if(in_state(GameState::Playing))
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_system(
+                end_interpolation
+                    .after(SimulationSet::Collisions)
+                    .run_if(in_state(GameState::Playing))
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_system(
+                interpolate_transforms
+                    .in_base_set(CoreSet::PostUpdate)
+                    .after(TransformSystem::TransformPropagate)
+                    .run_if(in_state(GameState::Playing)),
+            );
     }
 }
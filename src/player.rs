@@ -1,9 +1,14 @@
 use crate::actions::{Actions, ActionsSet};
 use crate::bullet::{BulletClip, SpawnBullet};
-use crate::enemy::Enemy;
+use crate::checkpoint::ReachedCheckpoint;
+use crate::lives::{Invulnerable, Lives};
 use crate::loading::AudioAssets;
-use crate::physics::{FixedOffset, UpdateCollisionShapes};
-use crate::player_rail::{PlayerRail, RailDirection, RailPosition};
+use crate::physics::{
+    CollisionBounds, CollisionEvent, CollisionLayer, CollisionMask, FixedOffset, Interpolated,
+    SimulationSet,
+};
+use crate::player_rail::{PlayerRail, RailBranch, RailPosition};
+use crate::score::Score;
 use crate::{GameState, LevelEntity};
 use bevy::prelude::*;
 use bevy_kira_audio::prelude::*;
@@ -19,19 +24,33 @@ pub struct Player;
 /// Player logic is only active during the State `GameState::Playing`
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(ShapePlugin)
-            .add_systems((spawn_player, spawn_rail).in_schedule(OnExit(GameState::Menu)))
+        app.register_type::<PlayerRail>()
+            .register_type::<Vec<Vec2>>()
+            .register_type::<RailBranch>()
+            .register_type::<Vec<RailBranch>>()
+            .add_system(spawn_player.in_schedule(OnEnter(GameState::LoadLevel)))
+            .add_system(after_deserialize_rail.in_set(OnUpdate(GameState::PostLoadLevel)))
             .add_systems(
-                (move_player, point_player, player_shoot)
+                (point_player, player_shoot)
                     .in_set(OnUpdate(GameState::Playing))
                     .after(ActionsSet),
             )
+            .add_system(
+                move_player
+                    .in_set(SimulationSet::Gameplay)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
             .add_system(
                 check_player_collisions_with_enemies
-                    .in_base_set(CoreSet::PostUpdate)
-                    .after(UpdateCollisionShapes),
+                    .in_set(SimulationSet::Collisions)
+                    .in_schedule(CoreSchedule::FixedUpdate),
             )
-            .add_system(back_to_menu.in_schedule(OnEnter(GameState::PlayerDead)));
+            // with a checkpoint reached the player is offered to continue instead
+            .add_system(
+                back_to_menu
+                    .run_if(not(resource_exists::<ReachedCheckpoint>()))
+                    .in_schedule(OnEnter(GameState::PlayerDead)),
+            );
     }
 }
 
@@ -49,11 +68,17 @@ fn spawn_player(mut commands: Commands) {
         Fill::color(Color::rgb_u8(199, 167, 37)),
         Player,
         CollisionShape::new_rectangle(8.0, 12.0),
-        RailPosition {
-            index: 0,
-            position: 0.0,
-            direction: RailDirection::Positive,
-        },
+        CollisionLayer::Player,
+        CollisionMask::new(&[
+            CollisionLayer::Enemy,
+            CollisionLayer::EnemyBullet,
+            CollisionLayer::Pickup,
+            CollisionLayer::Wall,
+        ]),
+        // the player turns freely, so the box has to hold the shape at any rotation
+        CollisionBounds(Vec2::splat(Vec2::new(4.0, 6.0).length())),
+        RailPosition::default(),
+        Interpolated::default(),
         LevelEntity,
     ));
 }
@@ -81,68 +106,74 @@ struct RailShapeStrokeBundle {
     level_entity: LevelEntity,
 }
 
-fn spawn_rail(mut commands: Commands) {
-    let rail_points = vec![Vec2::new(-110.0, 0.0), Vec2::new(110.0, 0.0)];
-    let rail_color = Color::rgb_u8(135, 188, 108);
-    let mut segments = vec![];
-    let mut points = vec![RailShapeFillBundle {
+fn rail_point_bundle(center: Vec2, color: Color, offset: Vec2) -> RailShapeFillBundle {
+    RailShapeFillBundle {
         tag: RailGraphic,
-
-        fill: Fill::color(rail_color),
+        fill: Fill::color(color),
         shape_bundle: ShapeBundle {
             path: GeometryBuilder::build_as(&shapes::Circle {
                 radius: 10.,
-                center: rail_points[0],
+                center,
             }),
             ..default()
         },
-        offset: FixedOffset(Vec2::new(0., -220.)),
+        offset: FixedOffset(offset),
         level_entity: LevelEntity,
-    }];
-
-    for (point1, point2) in rail_points[..rail_points.len() - 1]
-        .iter()
-        .zip(rail_points[1..].iter())
-    {
-        segments.push((RailShapeStrokeBundle {
-            tag: RailGraphic,
-            stroke: Stroke::new(rail_color, 5.0),
-            shape_bundle: ShapeBundle {
-                path: GeometryBuilder::build_as(&shapes::Line(*point1, *point2)),
-                ..default()
-            },
-            offset: FixedOffset(Vec2::new(0., -220.)),
-            level_entity: LevelEntity,
-        },));
-
-        points.push(RailShapeFillBundle {
-            tag: RailGraphic,
-            fill: Fill::color(rail_color),
-            shape_bundle: ShapeBundle {
-                path: GeometryBuilder::build_as(&shapes::Circle {
-                    radius: 10.,
-                    center: *point2,
-                }),
-                ..default()
-            },
-            offset: FixedOffset(Vec2::new(0., -220.)),
-            level_entity: LevelEntity,
-        });
     }
+}
 
-    commands.spawn_batch(segments);
-    commands.spawn_batch(points);
-    commands.spawn((
-        PlayerRail {
-            rail: rail_points,
-            closed: false,
+fn rail_segment_bundle(
+    point1: Vec2,
+    point2: Vec2,
+    color: Color,
+    offset: Vec2,
+) -> RailShapeStrokeBundle {
+    RailShapeStrokeBundle {
+        tag: RailGraphic,
+        stroke: Stroke::new(color, 5.0),
+        shape_bundle: ShapeBundle {
+            path: GeometryBuilder::build_as(&shapes::Line(point1, point2)),
+            ..default()
         },
-        LevelEntity,
-    ));
+        offset: FixedOffset(offset),
+        level_entity: LevelEntity,
+    }
+}
+
+// build the rail graphics for a rail loaded from the level file.
+// A broken rail is left out of the level, the player then stays where it spawned.
+fn after_deserialize_rail(
+    mut commands: Commands,
+    q: Query<(Entity, &PlayerRail, &FixedOffset), Without<LevelEntity>>,
+) {
+    let rail_color = Color::rgb_u8(135, 188, 108);
+    for (entity, rail, offset) in q.iter() {
+        if let Err(error) = rail.validate() {
+            warn!("ignoring invalid rail: {error}");
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let segments: Vec<_> = (0..rail.segment_count())
+            .map(|i| {
+                let (start, end) = rail.segment(i);
+                rail_segment_bundle(rail.node(start), rail.node(end), rail_color, offset.0)
+            })
+            .collect();
+        let points: Vec<_> = rail
+            .rail
+            .iter()
+            .chain(&rail.branch_nodes)
+            .map(|point| rail_point_bundle(*point, rail_color, offset.0))
+            .collect();
+
+        commands.spawn_batch(segments);
+        commands.spawn_batch(points);
+        commands.entity(entity).insert(LevelEntity);
+    }
 }
 
 fn move_player(
-    time: Res<Time>,
+    fixed_time: Res<FixedTime>,
     actions: Res<Actions>,
     mut player_query: Query<
         (&mut Transform, &mut RailPosition),
@@ -151,9 +182,13 @@ fn move_player(
     mut clip: Query<&mut BulletClip>,
     rail: Query<&PlayerRail>,
     rail_graphic: Query<&Transform, With<RailGraphic>>,
+    mut score: ResMut<Score>,
     audio_assets: Res<AudioAssets>,
     audio: Res<Audio>,
 ) {
+    let Ok(rail) = rail.get_single() else {
+        return;
+    };
     if player_query.is_empty() {
         return;
     }
@@ -172,11 +207,17 @@ fn move_player(
     let speed = 150.;
 
     let mut clip = clip.single_mut();
-    let rail = rail.single();
     let (mut player_transform, mut rail_position) = player_query.single_mut();
-    let (new_translation, at_node) = rail_position.next_position(rail, time.delta_seconds(), speed);
+    let (new_translation, at_node) = rail_position.next_position(
+        rail,
+        fixed_time.period.as_secs_f32(),
+        speed,
+        actions.player_movement,
+    );
     if at_node && !clip.full() {
         clip.reload();
+        // combos only last for a single clip
+        score.break_combo();
         audio.play(audio_assets.reload.clone());
     }
     let rail_transform = rail_graphic.iter().next().unwrap();
@@ -208,9 +249,7 @@ fn player_shoot(
         let t = player_query.single_mut();
         let mut clip = clip.single_mut();
         if clip.try_shoot() {
-            spawn_bullet.send(SpawnBullet {
-                initial_transform: *t,
-            });
+            spawn_bullet.send(SpawnBullet::player(*t));
             audio.play(asset_server.load("audio/shoot.wav"));
         } else {
             audio.play(asset_server.load("audio/empty_clip.wav"));
@@ -218,39 +257,67 @@ fn player_shoot(
     }
 }
 
+// Hits cost a life and send the player back to the start of the rail. The run is over without lives.
 fn check_player_collisions_with_enemies(
     mut commands: Commands,
-    player: Query<(Entity, &CollisionShape), (With<Player>, Without<Enemy>)>,
-    enemies: Query<(Entity, &CollisionShape), With<Enemy>>,
+    mut collisions: EventReader<CollisionEvent>,
+    mut player: Query<
+        (Entity, &mut Transform, &mut RailPosition),
+        (With<Player>, Without<Invulnerable>, Without<RailGraphic>),
+    >,
+    rail: Query<&PlayerRail>,
+    rail_graphic: Query<&Transform, With<RailGraphic>>,
+    layers: Query<&CollisionLayer>,
+    mut lives: ResMut<Lives>,
     mut state: ResMut<NextState<GameState>>,
     audio_assets: Option<Res<AudioAssets>>,
     audio: Res<Audio>,
 ) {
-    if player.iter().next().is_none() || audio_assets.is_none() {
+    // invulnerable players aren't in the query either
+    if player.is_empty() || audio_assets.is_none() {
+        collisions.clear();
         return;
     }
-    let (player_entity, player_shape) = player.single();
-    let player_death_sfx = audio_assets.unwrap().player_death.clone();
-    for (_enemy_entity, enemy_shape) in enemies.iter() {
-        if player_shape.is_collided_with(enemy_shape) {
-            commands.entity(player_entity).despawn();
-            audio.play(player_death_sfx.clone());
-            state.set(GameState::PlayerDead);
+    let (player_entity, mut player_transform, mut rail_position) = player.single_mut();
+    let mut hit = false;
+    // the player is on the first layer, so always comes first in an event
+    for collision in collisions
+        .iter()
+        .filter(|collision| collision.a == player_entity)
+    {
+        match layers.get(collision.b) {
+            Ok(CollisionLayer::Enemy) => hit = true,
+            // or it would hit again once the player is no longer invulnerable
+            Ok(CollisionLayer::EnemyBullet) => {
+                hit = true;
+                commands.entity(collision.b).despawn();
+            }
+            _ => {}
         }
     }
-}
+    if !hit {
+        return;
+    }
+    audio.play(audio_assets.unwrap().player_death.clone());
+    lives.0 = lives.0.saturating_sub(1);
+    if lives.0 == 0 {
+        commands.entity(player_entity).despawn();
+        state.set(GameState::PlayerDead);
+        return;
+    }
 
-fn back_to_menu(
-    mut state: ResMut<NextState<GameState>>,
-    q: Query<Entity, With<LevelEntity>>,
-    mut camera: Query<&mut Transform, With<Camera>>,
-    mut commands: Commands,
-) {
-    state.set(GameState::Menu);
-    for e in &q {
-        commands.entity(e).despawn();
+    *rail_position = RailPosition::default();
+    if let Ok(rail) = rail.get_single() {
+        let (start, _) = rail.segment(0);
+        let rail_transform = rail_graphic.iter().next().unwrap();
+        player_transform.translation =
+            rail.node(start).extend(0.0) + rail_transform.translation.y * Vec3::Y;
     }
+    commands
+        .entity(player_entity)
+        .insert(Invulnerable::default());
+}
 
-    let mut transform = camera.single_mut();
-    transform.translation = Vec3::new(0.0, 0.0, transform.translation.z);
+fn back_to_menu(mut state: ResMut<NextState<GameState>>) {
+    state.set(GameState::Menu);
 }
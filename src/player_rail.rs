@@ -1,12 +1,117 @@
+use std::fmt;
+
 use bevy::prelude::*;
 
-#[derive(Component)]
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
 pub struct PlayerRail {
     pub rail: Vec<Vec2>,
     // is the rail a closed path
     pub closed: bool,
+    /// extra segments between nodes. Nodes they touch become junctions.
+    pub branches: Vec<RailBranch>,
+    /// nodes only reached through branches, numbered after the nodes of `rail`
+    pub branch_nodes: Vec<Vec2>,
+}
+
+/// A segment from node `from` to node `to` outside of the main rail path
+#[derive(Default, Reflect, FromReflect, Clone, Copy, Debug, PartialEq)]
+pub struct RailBranch {
+    pub from: usize,
+    pub to: usize,
+}
+
+/// Why the player can't move along a rail
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RailError {
+    /// a rail needs at least two nodes to have a segment
+    TooFewNodes(usize),
+    /// the branch at this index starts or ends at a node the rail doesn't have
+    BranchOutOfRange(usize),
 }
 
+impl fmt::Display for RailError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RailError::TooFewNodes(nodes) => write!(f, "rail has {nodes} nodes, needs at least 2"),
+            RailError::BranchOutOfRange(branch) => {
+                write!(f, "branch {branch} connects a node the rail doesn't have")
+            }
+        }
+    }
+}
+
+impl PlayerRail {
+    /// Checks that every segment is between two nodes of the rail,
+    /// which `segment` and `RailPosition::next_position` rely on
+    pub fn validate(&self) -> Result<(), RailError> {
+        if self.rail.len() < 2 {
+            return Err(RailError::TooFewNodes(self.rail.len()));
+        }
+        let nodes = self.rail.len() + self.branch_nodes.len();
+        match self
+            .branches
+            .iter()
+            .position(|branch| branch.from.max(branch.to) >= nodes)
+        {
+            Some(branch) => Err(RailError::BranchOutOfRange(branch)),
+            None => Ok(()),
+        }
+    }
+
+    /// Position of a node, either on the rail path or only reached through branches
+    pub fn node(&self, index: usize) -> Vec2 {
+        match self.rail.get(index) {
+            Some(node) => *node,
+            None => self.branch_nodes[index - self.rail.len()],
+        }
+    }
+
+    // number of segments along the rail path, including the one closing the loop
+    fn path_segment_count(&self) -> usize {
+        let open_segments = self.rail.len().saturating_sub(1);
+        if self.closed && self.rail.len() > 2 {
+            open_segments + 1
+        } else {
+            open_segments
+        }
+    }
+
+    pub fn segment_count(&self) -> usize {
+        self.path_segment_count() + self.branches.len()
+    }
+
+    /// Node indices at the start and end of a segment.
+    /// Segments along the rail path come first, then the branches.
+    pub fn segment(&self, index: usize) -> (usize, usize) {
+        let path_segments = self.path_segment_count();
+        if index < path_segments {
+            (index, (index + 1) % self.rail.len())
+        } else {
+            let branch = self.branches[index - path_segments];
+            (branch.from, branch.to)
+        }
+    }
+
+    // the segment and direction to continue on after arriving at `node` from `segment`
+    fn exit(&self, node: usize, segment: usize, preferred: Vec2) -> Option<(usize, RailDirection)> {
+        (0..self.segment_count())
+            .filter(|&index| index != segment)
+            .filter_map(|index| match self.segment(index) {
+                (start, end) if start == node => Some((index, RailDirection::Positive, end)),
+                (start, end) if end == node => Some((index, RailDirection::Negative, start)),
+                _ => None,
+            })
+            .max_by(|(_, _, a), (_, _, b)| {
+                let a = (self.node(*a) - self.node(node)).normalize_or_zero();
+                let b = (self.node(*b) - self.node(node)).normalize_or_zero();
+                a.dot(preferred).total_cmp(&b.dot(preferred))
+            })
+            .map(|(index, direction, _)| (index, direction))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum RailDirection {
     Positive,
     Negative,
@@ -22,36 +127,82 @@ pub struct RailPosition {
     pub direction: RailDirection,
 }
 
+impl Default for RailPosition {
+    /// the start of the rail
+    fn default() -> Self {
+        RailPosition {
+            index: 0,
+            position: 0.0,
+            direction: RailDirection::Positive,
+        }
+    }
+}
+
 impl RailPosition {
+    /// Moves along the rail by `speed * delta_time`, carrying over to the next segment at nodes.
+    /// At junctions the branch closest to `steer` is taken, or the straightest one when there is
+    /// no steering. Movement reverses at dead ends.
+    /// Returns the new position and whether a node was reached.
     pub fn next_position(
         &mut self,
         rail: &PlayerRail,
         delta_time: f32,
         speed: f32,
+        steer: Option<Vec2>,
     ) -> (Vec2, bool) {
-        let index = self.index;
-        let segment = rail.rail[index + 1] - rail.rail[index];
-        let delta_position = speed * delta_time / segment.length();
+        let mut distance = speed * delta_time;
         let mut at_node = false;
-        match self.direction {
-            RailDirection::Negative => {
-                self.position -= delta_position;
-                if self.position < 0.0 {
-                    self.position = 0.0;
-                    self.direction = RailDirection::Positive;
-                    at_node = true;
+        // bounds the work done when a frame covers the whole rail, e.g. after a long hitch
+        for _ in 0..=rail.segment_count() * 2 {
+            let (start, end) = rail.segment(self.index);
+            let segment = rail.node(end) - rail.node(start);
+            let length = segment.length();
+            let remaining = match self.direction {
+                RailDirection::Positive => (1.0 - self.position) * length,
+                RailDirection::Negative => self.position * length,
+            };
+            if distance < remaining {
+                let delta_position = distance / length;
+                match self.direction {
+                    RailDirection::Positive => self.position += delta_position,
+                    RailDirection::Negative => self.position -= delta_position,
                 }
-                (rail.rail[index] + segment * self.position, at_node)
+                break;
             }
-            RailDirection::Positive => {
-                self.position += delta_position;
-                if self.position > 1.0 {
-                    self.position = 1.0;
-                    self.direction = RailDirection::Negative;
-                    at_node = true;
+
+            distance -= remaining;
+            at_node = true;
+            let (node, travel) = match self.direction {
+                RailDirection::Positive => (end, segment),
+                RailDirection::Negative => (start, -segment),
+            };
+            let preferred = steer.unwrap_or(travel).normalize_or_zero();
+            match rail.exit(node, self.index, preferred) {
+                Some((index, direction)) => {
+                    self.index = index;
+                    self.direction = direction;
+                    self.position = match direction {
+                        RailDirection::Positive => 0.0,
+                        RailDirection::Negative => 1.0,
+                    };
+                }
+                // dead end, turn around
+                None => {
+                    (self.position, self.direction) = match self.direction {
+                        RailDirection::Positive => (1.0, RailDirection::Negative),
+                        RailDirection::Negative => (0.0, RailDirection::Positive),
+                    };
                 }
-                (rail.rail[index] + segment * self.position, at_node)
+            }
+            if distance <= 0.0 {
+                break;
             }
         }
+
+        let (start, end) = rail.segment(self.index);
+        (
+            rail.node(start).lerp(rail.node(end), self.position),
+            at_node,
+        )
     }
 }
@@ -0,0 +1,93 @@
+use bevy::core_pipeline::clear_color::ClearColorConfig;
+use bevy::prelude::*;
+use bevy::render::camera::{ScalingMode, Viewport};
+use bevy::render::view::RenderLayers;
+use bevy::window::PrimaryWindow;
+
+use crate::constants::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::physics::Velocity;
+
+// nothing in the world is on this layer, so the UI camera draws only the UI
+const UI_LAYER: u8 = 1;
+
+/// Shows exactly `SCREEN_WIDTH` by `SCREEN_HEIGHT` world units, whatever the window's size
+pub(crate) fn playfield_projection() -> OrthographicProjection {
+    OrthographicProjection {
+        scaling_mode: ScalingMode::Fixed {
+            width: SCREEN_WIDTH,
+            height: SCREEN_HEIGHT,
+        },
+        ..default()
+    }
+}
+
+// Scales the playfield as large as it fits in the window without stretching,
+// leaving bars on the sides that don't match its shape.
+fn fit_viewport(
+    window: Query<&Window, (With<PrimaryWindow>, Changed<Window>)>,
+    mut camera: Query<&mut Camera, With<Velocity>>,
+) {
+    let Ok(window) = window.get_single() else {
+        return;
+    };
+    let window_size = UVec2::new(window.physical_width(), window.physical_height());
+    let scale = (window_size.x as f32 / SCREEN_WIDTH).min(window_size.y as f32 / SCREEN_HEIGHT);
+    let size = (Vec2::new(SCREEN_WIDTH, SCREEN_HEIGHT) * scale)
+        .as_uvec2()
+        .clamp(UVec2::ONE, window_size.max(UVec2::ONE));
+    let viewport = Viewport {
+        physical_position: (window_size.max(size) - size) / 2,
+        physical_size: size,
+        ..default()
+    };
+    for mut camera in camera.iter_mut() {
+        // only write on a real change, the window changes whenever the cursor moves
+        let unchanged = camera.viewport.as_ref().is_some_and(|current| {
+            current.physical_position == viewport.physical_position
+                && current.physical_size == viewport.physical_size
+        });
+        if !unchanged {
+            camera.viewport = Some(viewport.clone());
+        }
+    }
+}
+
+// UI is laid out over the whole window, so it can't be drawn by the letterboxed camera
+fn setup_ui_camera(mut commands: Commands) {
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                order: 1,
+                ..default()
+            },
+            camera_2d: Camera2d {
+                clear_color: ClearColorConfig::None,
+            },
+            ..default()
+        },
+        RenderLayers::layer(UI_LAYER),
+    ));
+}
+
+/// The point in the world under the cursor, or `None` when it isn't over the window
+pub(crate) fn cursor_to_world(
+    window: &Window,
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+) -> Option<Vec2> {
+    // the cursor is measured from the bottom left of the window, the viewport from the top left
+    let cursor = window.cursor_position()?;
+    let (min, max) = camera.logical_viewport_rect()?;
+    let in_viewport = Vec2::new(cursor.x - min.x, cursor.y - (window.height() - max.y));
+    camera.viewport_to_world_2d(camera_transform, in_viewport)
+}
+
+/// Keeps the playfield letterboxed in the window. Needs a window, unlike the rest of the game.
+/// Can be added on top of `HeadlessGamePlugin` together with a `PrimaryWindow`.
+pub struct PlayfieldPlugin;
+impl Plugin for PlayfieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup_ui_camera.on_startup())
+            .add_system(fit_viewport);
+    }
+}
@@ -0,0 +1,256 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use serde::{Deserialize, Serialize};
+
+use crate::actions::{Actions, ActionsSet};
+use crate::enemy::Enemy;
+use crate::level::CurrentLevel;
+use crate::player::Player;
+use crate::score::Score;
+use crate::GameState;
+
+/// Inputs and frame time of a single frame of play
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReplayFrame {
+    pub delta: Duration,
+    pub player_movement: Option<Vec2>,
+    pub player_point: Option<Vec2>,
+    pub player_stop: bool,
+    pub player_shoot: bool,
+}
+
+/// State of the game when a replay ends, compared when verifying a playback
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReplayEnd {
+    /// true if the level was finished, false if the player died
+    pub cleared: bool,
+    pub player: Option<Vec2>,
+    pub enemies: usize,
+    pub score: u32,
+}
+
+/// Every frame of one attempt at a level
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Replay {
+    /// index into the `LevelManifest`
+    pub level: usize,
+    pub frames: Vec<ReplayFrame>,
+    pub end: Option<ReplayEnd>,
+}
+
+impl Replay {
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        ron::from_str(&contents)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let contents = ron::ser::to_string_pretty(self, default())
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+        fs::write(path, contents)
+    }
+}
+
+/// How the last playback ended compared to its recording
+#[derive(Resource, Debug)]
+pub struct ReplayResult {
+    pub expected: Option<ReplayEnd>,
+    pub actual: ReplayEnd,
+}
+
+impl ReplayResult {
+    pub fn matches(&self) -> bool {
+        self.expected.as_ref() == Some(&self.actual)
+    }
+}
+
+/// Inserting this resource records or plays back replays. Without it the game is played normally.
+#[derive(Resource)]
+pub enum ReplayMode {
+    /// Records each level played, overwriting `path` whenever a level ends.
+    /// With a `fixed_delta` every frame is simulated as taking that long, however long it really took.
+    Record {
+        path: PathBuf,
+        fixed_delta: Option<Duration>,
+        replay: Replay,
+    },
+    /// Plays `replay` from the menu, feeding `Actions` from the replay instead of input devices.
+    /// When `verify` is set, panics if the level doesn't end in the recorded state.
+    /// Either way the outcome is left in `ReplayResult`.
+    Playback {
+        replay: Replay,
+        frame: usize,
+        verify: bool,
+    },
+}
+
+impl ReplayMode {
+    pub fn record(path: impl Into<PathBuf>, fixed_delta: Option<Duration>) -> Self {
+        ReplayMode::Record {
+            path: path.into(),
+            fixed_delta,
+            replay: default(),
+        }
+    }
+
+    pub fn playback(replay: Replay, verify: bool) -> Self {
+        ReplayMode::Playback {
+            replay,
+            frame: 0,
+            verify,
+        }
+    }
+}
+
+/// Run condition for systems reading input devices, which are ignored during playback
+pub fn reads_input_devices(mode: Option<Res<ReplayMode>>) -> bool {
+    !matches!(mode.as_deref(), Some(ReplayMode::Playback { .. }))
+}
+
+fn start_recording(mode: Option<ResMut<ReplayMode>>, current_level: Res<CurrentLevel>) {
+    if let Some(ReplayMode::Record { replay, .. }) = mode.map(ResMut::into_inner) {
+        *replay = Replay {
+            level: current_level.0,
+            ..default()
+        };
+    }
+}
+
+fn record_frame(mode: Option<ResMut<ReplayMode>>, actions: Res<Actions>, time: Res<Time>) {
+    if let Some(ReplayMode::Record { replay, .. }) = mode.map(ResMut::into_inner) {
+        replay.frames.push(ReplayFrame {
+            delta: time.delta(),
+            player_movement: actions.player_movement,
+            player_point: actions.player_point,
+            player_stop: actions.player_stop,
+            player_shoot: actions.player_shoot,
+        });
+    }
+}
+
+// playback skips the menu and goes straight to the recorded level
+fn start_playback(
+    mode: Option<Res<ReplayMode>>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    if let Some(ReplayMode::Playback { replay, .. }) = mode.as_deref() {
+        current_level.0 = replay.level;
+        state.set(GameState::LoadLevel);
+    }
+}
+
+fn play_back_frame(mode: Option<ResMut<ReplayMode>>, mut actions: ResMut<Actions>) {
+    if let Some(ReplayMode::Playback { replay, frame, .. }) = mode.map(ResMut::into_inner) {
+        // running past the end of the replay means it already diverged, stop giving input
+        if *frame >= replay.frames.len() {
+            *actions = default();
+            return;
+        }
+        let recorded = &replay.frames[*frame];
+        actions.player_movement = recorded.player_movement;
+        actions.player_point = recorded.player_point;
+        actions.player_stop = recorded.player_stop;
+        actions.player_shoot = recorded.player_shoot;
+        *frame += 1;
+    }
+}
+
+// Time is advanced at the start of a frame, so the next frame's delta is set up a frame ahead.
+// `ManualDuration` adds to the real time rather than the last update, so instants are used instead.
+fn set_replay_time(
+    mode: Option<Res<ReplayMode>>,
+    state: Res<State<GameState>>,
+    time: Res<Time>,
+    mut time_strategy: ResMut<TimeUpdateStrategy>,
+) {
+    let next_delta = match mode.as_deref() {
+        Some(ReplayMode::Record { fixed_delta, .. }) => *fixed_delta,
+        Some(ReplayMode::Playback { replay, frame, .. })
+            if matches!(state.0, GameState::PostLoadLevel | GameState::Playing) =>
+        {
+            // a diverged replay can run past its end, keep time going at the last recorded rate
+            let next = replay.frames.get(*frame).or(replay.frames.last());
+            next.map(|next| next.delta)
+        }
+        _ => None,
+    };
+    if let (Some(delta), Some(last_update)) = (next_delta, time.last_update()) {
+        *time_strategy = TimeUpdateStrategy::ManualInstant(last_update + delta);
+    }
+}
+
+// The fixed timestep carries time over between frames. Starting every level with none left over
+// keeps the simulation ticking on the same frames in playback as it did while recording,
+// whatever happened in the menu and while loading.
+fn reset_fixed_time(mode: Option<Res<ReplayMode>>, mut fixed_time: ResMut<FixedTime>) {
+    if mode.is_some() {
+        *fixed_time = FixedTime::new(fixed_time.period);
+    }
+}
+
+fn finish_replay(
+    mut commands: Commands,
+    mode: Option<ResMut<ReplayMode>>,
+    state: Res<State<GameState>>,
+    player: Query<&Transform, With<Player>>,
+    enemies: Query<(), With<Enemy>>,
+    score: Res<Score>,
+    mut time_strategy: ResMut<TimeUpdateStrategy>,
+) {
+    if mode.is_none() {
+        return;
+    }
+    let end = ReplayEnd {
+        cleared: state.0 == GameState::LevelFinished,
+        player: player.get_single().ok().map(|t| t.translation.truncate()),
+        enemies: enemies.iter().count(),
+        score: score.points,
+    };
+    match mode.unwrap().into_inner() {
+        ReplayMode::Record { path, replay, .. } => {
+            replay.end = Some(end);
+            if let Err(error) = replay.save(&*path) {
+                warn!("failed to save replay to {path:?}: {error}");
+            }
+        }
+        ReplayMode::Playback { replay, verify, .. } => {
+            let result = ReplayResult {
+                expected: replay.end.clone(),
+                actual: end,
+            };
+            if !result.matches() {
+                if *verify {
+                    panic!("replay diverged from the recording: {result:?}");
+                }
+                warn!("replay diverged from the recording: {result:?}");
+            }
+            commands.insert_resource(result);
+            commands.remove_resource::<ReplayMode>();
+            *time_strategy = TimeUpdateStrategy::Automatic;
+        }
+    }
+}
+
+pub struct ReplayPlugin;
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(start_recording.in_schedule(OnEnter(GameState::LoadLevel)))
+            .add_system(reset_fixed_time.in_schedule(OnEnter(GameState::Playing)))
+            .add_system(start_playback.in_set(OnUpdate(GameState::Menu)))
+            .add_system(play_back_frame.in_set(ActionsSet))
+            .add_system(
+                record_frame
+                    .in_set(OnUpdate(GameState::Playing))
+                    .after(ActionsSet),
+            )
+            .add_system(set_replay_time.in_base_set(CoreSet::Last))
+            .add_system(finish_replay.in_schedule(OnEnter(GameState::LevelFinished)))
+            .add_system(finish_replay.in_schedule(OnEnter(GameState::PlayerDead)));
+    }
+}
@@ -0,0 +1,156 @@
+use bevy::prelude::*;
+
+use crate::level::{CurrentLevel, LevelManifest};
+use crate::loading::{FontAssets, LevelAssets};
+use crate::menu::{ButtonColors, ButtonInteraction};
+use crate::GameState;
+
+#[derive(Component)]
+struct ResultsMenu;
+
+#[derive(Component)]
+struct NextLevelButton;
+#[derive(Component)]
+struct MenuButton;
+
+fn setup_results_menu(
+    mut commands: Commands,
+    button_colors: Res<ButtonColors>,
+    font_assets: Res<FontAssets>,
+    level_assets: Res<LevelAssets>,
+    manifests: Res<Assets<LevelManifest>>,
+    current_level: Res<CurrentLevel>,
+) {
+    let manifest = manifests.get(&level_assets.manifest).unwrap();
+    let has_next_level = current_level.0 + 1 < manifest.levels.len();
+
+    let text_style = TextStyle {
+        font: font_assets.fira_sans.clone(),
+        font_size: 40.0,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_content: AlignContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                background_color: Color::NONE.into(),
+                ..Default::default()
+            },
+            ResultsMenu,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                text: Text {
+                    sections: vec![TextSection {
+                        value: format!("{} clear", manifest.levels[current_level.0].name),
+                        style: TextStyle {
+                            font_size: 30.0,
+                            color: Color::rgb(0.15, 0.15, 0.15),
+                            ..text_style.clone()
+                        },
+                    }],
+                    alignment: TextAlignment::Center,
+                    ..default()
+                },
+                ..Default::default()
+            });
+
+            let button_bundle = ButtonBundle {
+                style: Style {
+                    size: Size::new(Val::Px(120.0), Val::Px(50.0)),
+                    margin: UiRect::top(Val::Px(10.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                background_color: button_colors.normal,
+                ..Default::default()
+            };
+
+            let (label, mut button) = if has_next_level {
+                ("Next", parent.spawn((button_bundle, NextLevelButton)))
+            } else {
+                ("Menu", parent.spawn((button_bundle, MenuButton)))
+            };
+            button.with_children(|parent| {
+                parent.spawn(TextBundle {
+                    text: Text {
+                        sections: vec![TextSection {
+                            value: label.to_string(),
+                            style: text_style.clone(),
+                        }],
+                        alignment: TextAlignment::Left,
+                        ..default()
+                    },
+                    ..Default::default()
+                });
+            });
+        });
+}
+
+fn despawn_results_menu(mut commands: Commands, q: Query<Entity, With<ResultsMenu>>) {
+    for e in q.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn hover_button(
+    button_colors: Res<ButtonColors>,
+    mut interaction_query: Query<ButtonInteraction, (Changed<Interaction>, With<Button>)>,
+) {
+    for (_button, interaction, mut color, _children) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Hovered => {
+                *color = button_colors.hovered;
+            }
+            Interaction::None => {
+                *color = button_colors.normal;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn click_next_level_button(
+    mut interaction_query: Query<ButtonInteraction, (Changed<Interaction>, With<NextLevelButton>)>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for (_button, interaction, _color, _children) in interaction_query.iter_mut() {
+        if *interaction == Interaction::Clicked {
+            current_level.0 += 1;
+            game_state.set(GameState::LoadLevel);
+        }
+    }
+}
+
+fn click_menu_button(
+    mut interaction_query: Query<ButtonInteraction, (Changed<Interaction>, With<MenuButton>)>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for (_button, interaction, _color, _children) in interaction_query.iter_mut() {
+        if *interaction == Interaction::Clicked {
+            game_state.set(GameState::Menu);
+        }
+    }
+}
+
+pub struct ResultsMenuPlugin;
+impl Plugin for ResultsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup_results_menu.in_schedule(OnEnter(GameState::LevelFinished)))
+            .add_systems(
+                (hover_button, click_next_level_button, click_menu_button)
+                    .in_set(OnUpdate(GameState::LevelFinished)),
+            )
+            .add_system(despawn_results_menu.in_schedule(OnExit(GameState::LevelFinished)));
+    }
+}
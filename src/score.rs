@@ -0,0 +1,213 @@
+use std::fs;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::loading::FontAssets;
+use crate::physics::FixedOffset;
+use crate::{GameState, LevelEntity};
+
+// number of scores kept in the high score table
+const HIGH_SCORE_COUNT: usize = 10;
+
+/// Points scored in the current run, carried over between levels
+#[derive(Resource, Default, Debug)]
+pub struct Score {
+    pub points: u32,
+    /// consecutive hits since the last miss or reload
+    pub combo: u32,
+}
+
+impl Score {
+    /// Counts a bullet hitting, awarding `points` for the enemies it destroyed times the combo
+    pub fn hit(&mut self, points: u32) {
+        self.combo += 1;
+        self.points += points * self.combo;
+    }
+
+    pub fn break_combo(&mut self) {
+        self.combo = 0;
+    }
+}
+
+/// Best scores, highest first
+#[derive(Resource, Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct HighScores {
+    pub scores: Vec<u32>,
+}
+
+impl HighScores {
+    /// Adds `points` to the table, returning false if it didn't make the cut
+    pub fn insert(&mut self, points: u32) -> bool {
+        let index = self.scores.partition_point(|&score| score >= points);
+        if index >= HIGH_SCORE_COUNT {
+            return false;
+        }
+        self.scores.insert(index, points);
+        self.scores.truncate(HIGH_SCORE_COUNT);
+        true
+    }
+}
+
+/// Where the high score table is saved. Defaults to the user's data directory.
+#[derive(Resource)]
+pub struct HighScoreFile(pub PathBuf);
+
+impl Default for HighScoreFile {
+    fn default() -> Self {
+        let data_dir = dirs::data_dir().unwrap_or_else(|| PathBuf::from("."));
+        HighScoreFile(data_dir.join("shoe_shmup").join("high_scores.ron"))
+    }
+}
+
+#[derive(Component)]
+struct ScoreText;
+
+#[derive(Component)]
+struct HighScoreTable;
+
+fn load_high_scores(mut commands: Commands, file: Res<HighScoreFile>) {
+    // no file yet just means no games have been played
+    let high_scores = fs::read_to_string(&file.0)
+        .ok()
+        .and_then(|contents| match ron::from_str(&contents) {
+            Ok(high_scores) => Some(high_scores),
+            Err(error) => {
+                warn!("ignoring invalid high scores in {:?}: {error}", file.0);
+                None
+            }
+        })
+        .unwrap_or_default();
+    commands.insert_resource::<HighScores>(high_scores);
+}
+
+fn save_high_scores(file: &HighScoreFile, high_scores: &HighScores) -> std::io::Result<()> {
+    if let Some(dir) = file.0.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let contents = ron::ser::to_string_pretty(high_scores, default())
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+    fs::write(&file.0, contents)
+}
+
+// ends the run when returning to the menu
+fn record_high_score(
+    mut score: ResMut<Score>,
+    mut high_scores: ResMut<HighScores>,
+    file: Res<HighScoreFile>,
+) {
+    let points = std::mem::take(&mut *score).points;
+    if points == 0 || !high_scores.insert(points) {
+        return;
+    }
+    if let Err(error) = save_high_scores(&file, &high_scores) {
+        warn!("failed to save high scores to {:?}: {error}", file.0);
+    }
+}
+
+fn setup_high_score_table(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    high_scores: Res<HighScores>,
+) {
+    if high_scores.scores.is_empty() {
+        return;
+    }
+    let mut value = "High scores".to_string();
+    for (rank, points) in high_scores.scores.iter().enumerate() {
+        value += &format!("\n{}. {points}", rank + 1);
+    }
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect::top(Val::Px(20.0)),
+                    size: Size::width(Val::Percent(100.0)),
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            HighScoreTable,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                text: Text {
+                    sections: vec![TextSection {
+                        value,
+                        style: TextStyle {
+                            font: font_assets.fira_sans.clone(),
+                            font_size: 24.0,
+                            color: Color::rgb(0.15, 0.15, 0.15),
+                        },
+                    }],
+                    alignment: TextAlignment::Center,
+                    ..default()
+                },
+                ..Default::default()
+            });
+        });
+}
+
+fn despawn_high_score_table(mut commands: Commands, q: Query<Entity, With<HighScoreTable>>) {
+    for e in q.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn score_text(score: &Score) -> String {
+    if score.combo > 1 {
+        format!("{} x{}", score.points, score.combo)
+    } else {
+        score.points.to_string()
+    }
+}
+
+// shows the score on screen, next to the bullet clip
+fn spawn_score_text(mut commands: Commands, font_assets: Res<FontAssets>, score: Res<Score>) {
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                score_text(&score),
+                TextStyle {
+                    font: font_assets.fira_sans.clone(),
+                    font_size: 20.0,
+                    color: Color::rgb(0.0, 0.0, 0.0),
+                },
+            ),
+            ..default()
+        },
+        FixedOffset(Vec2::new(60.0, -234.0)),
+        ScoreText,
+        LevelEntity,
+    ));
+}
+
+fn update_score_text(score: Res<Score>, mut text: Query<&mut Text, With<ScoreText>>) {
+    if !score.is_changed() {
+        return;
+    }
+    for mut text in text.iter_mut() {
+        text.sections[0].value = score_text(&score);
+    }
+}
+
+pub struct ScorePlugin;
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Score>()
+            .init_resource::<HighScoreFile>()
+            .add_system(load_high_scores.on_startup())
+            .add_systems(
+                (record_high_score, setup_high_score_table)
+                    .chain()
+                    .in_schedule(OnEnter(GameState::Menu)),
+            )
+            .add_system(despawn_high_score_table.in_schedule(OnExit(GameState::Menu)))
+            .add_system(spawn_score_text.in_schedule(OnEnter(GameState::LoadLevel)))
+            .add_system(update_score_text.in_set(OnUpdate(GameState::Playing)));
+    }
+}
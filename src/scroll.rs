@@ -0,0 +1,154 @@
+use bevy::prelude::*;
+
+use crate::constants::SCREEN_HEIGHT;
+use crate::enemy::Enemy;
+use crate::physics::{SimulationSet, Velocity};
+use crate::{GameState, LevelEntity};
+
+/// Scrolling speed of levels without a `ScrollTimeline`
+pub(crate) const DEFAULT_SCROLL_SPEED: f32 = 20.0;
+
+/// How fast the camera scrolls at each point of a level.
+/// Between keyframes the speed changes linearly with the camera's y position.
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct ScrollTimeline {
+    /// sorted by `y`
+    pub keyframes: Vec<ScrollKeyframe>,
+}
+
+#[derive(Default, Reflect, FromReflect, Clone, Copy, Debug, PartialEq)]
+pub struct ScrollKeyframe {
+    pub y: f32,
+    pub speed: f32,
+    /// the camera stops at `y` before going on
+    pub pause: Option<ScrollPause>,
+}
+
+#[derive(Reflect, FromReflect, Clone, Copy, Debug, PartialEq)]
+pub enum ScrollPause {
+    Seconds(f32),
+    /// until every enemy on screen is destroyed, e.g. for a boss fight
+    UntilCleared,
+}
+
+impl ScrollTimeline {
+    /// Speed of the camera at `y`, ignoring pauses
+    pub fn speed_at(&self, y: f32) -> f32 {
+        let next = self.keyframes.iter().position(|keyframe| keyframe.y > y);
+        match next {
+            None => self
+                .keyframes
+                .last()
+                .map_or(DEFAULT_SCROLL_SPEED, |keyframe| keyframe.speed),
+            Some(0) => self.keyframes[0].speed,
+            Some(next) => {
+                let (from, to) = (self.keyframes[next - 1], self.keyframes[next]);
+                let t = (y - from.y) / (to.y - from.y);
+                from.speed + (to.speed - from.speed) * t
+            }
+        }
+    }
+}
+
+// how far the camera got along the timeline
+#[derive(Component, Default)]
+struct ScrollProgress {
+    // keyframes the camera has passed, including their pauses
+    passed: usize,
+    // seconds spent in the pause at the next keyframe
+    paused_for: Option<f32>,
+}
+
+fn after_deserialize_scroll_timeline(
+    mut commands: Commands,
+    q: Query<(Entity, &ScrollTimeline), Without<LevelEntity>>,
+    camera: Query<&Transform, (With<Camera>, With<Velocity>)>,
+) {
+    // levels continued from a checkpoint start part way along the timeline
+    let camera_y = camera.single().translation.y;
+    for (entity, timeline) in q.iter() {
+        let passed = timeline
+            .keyframes
+            .iter()
+            .take_while(|keyframe| keyframe.y < camera_y)
+            .count();
+        commands.entity(entity).insert((
+            LevelEntity,
+            ScrollProgress {
+                passed,
+                paused_for: None,
+            },
+        ));
+    }
+}
+
+fn follow_scroll_timeline(
+    mut timelines: Query<(&ScrollTimeline, &mut ScrollProgress)>,
+    mut camera: Query<(&mut Transform, &mut Velocity), With<Camera>>,
+    enemies: Query<&Transform, (With<Enemy>, Without<Camera>)>,
+    fixed_time: Res<FixedTime>,
+) {
+    let (mut camera_transform, mut velocity) = camera.single_mut();
+    let Ok((timeline, mut progress)) = timelines.get_single_mut() else {
+        velocity.0 = Vec2::new(0.0, DEFAULT_SCROLL_SPEED);
+        return;
+    };
+
+    if let Some(paused_for) = progress.paused_for {
+        let paused_for = paused_for + fixed_time.period.as_secs_f32();
+        let screen_top = camera_transform.translation.y + SCREEN_HEIGHT / 2.0;
+        let screen_bottom = camera_transform.translation.y - SCREEN_HEIGHT / 2.0;
+        let done = match timeline.keyframes[progress.passed].pause {
+            None => true,
+            Some(ScrollPause::Seconds(seconds)) => paused_for >= seconds,
+            Some(ScrollPause::UntilCleared) => enemies
+                .iter()
+                .all(|t| t.translation.y > screen_top || t.translation.y < screen_bottom),
+        };
+        if !done {
+            progress.paused_for = Some(paused_for);
+            velocity.0 = Vec2::ZERO;
+            return;
+        }
+        progress.paused_for = None;
+        progress.passed += 1;
+    }
+
+    let y = camera_transform.translation.y;
+    while let Some(keyframe) = timeline.keyframes.get(progress.passed) {
+        if keyframe.y > y {
+            break;
+        }
+        if keyframe.pause.is_some() {
+            // the camera may have moved past the keyframe during the last tick
+            camera_transform.translation.y = keyframe.y;
+            velocity.0 = Vec2::ZERO;
+            progress.paused_for = Some(0.0);
+            return;
+        }
+        progress.passed += 1;
+    }
+    velocity.0 = Vec2::new(0.0, timeline.speed_at(y));
+}
+
+pub struct ScrollPlugin;
+impl Plugin for ScrollPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<ScrollTimeline>()
+            .register_type::<ScrollKeyframe>()
+            .register_type::<ScrollPause>()
+            .register_type::<Option<ScrollPause>>()
+            .register_type::<Vec<ScrollKeyframe>>()
+            .add_system(
+                after_deserialize_scroll_timeline.in_set(OnUpdate(GameState::PostLoadLevel)),
+            )
+            // snaps the camera to pauses, so it has to run before anything else looks at the camera
+            .add_system(
+                follow_scroll_timeline
+                    .before(SimulationSet::Physics)
+                    .run_if(in_state(GameState::Playing))
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            );
+    }
+}
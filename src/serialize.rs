@@ -1,62 +1,212 @@
+use std::any::TypeId;
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::PathBuf;
+
 use async_compat::Compat;
+use bevy::ecs::entity::EntityMap;
+use bevy::ecs::event::ManualEventReader;
 use bevy::ecs::system::{SystemParam, SystemState};
 use bevy::prelude::*;
 use bevy::reflect::TypeRegistryArc;
-use bevy::scene::DynamicEntity;
+use bevy::scene::serde::SceneDeserializer;
+use bevy::scene::{scene_spawner_system, DynamicEntity};
 use bevy::tasks::{IoTaskPool, Task};
 use futures_lite::future;
+use serde::de::DeserializeSeed;
 use tokio::fs;
 
-use crate::enemy::Enemy;
+use crate::checkpoint::{Checkpoint, ReachedCheckpoint};
+use crate::editor::EditorLevel;
+use crate::enemy::{Enemy, EnemyKind};
+use crate::level::{CurrentLevel, LevelEnd, LevelManifest};
+use crate::loading::LevelAssets;
+use crate::physics::FixedOffset;
+use crate::player_rail::PlayerRail;
+use crate::scroll::ScrollTimeline;
+use crate::spawn::SpawnTrigger;
 use crate::GameState;
 
-pub struct SaveSceneEvent;
+/// Saves the current level as a scene file at `path`.
+/// Saving happens in the background and ends with a `SaveSceneCompleted` or `SaveSceneFailed`.
+pub struct SaveSceneEvent {
+    pub path: PathBuf,
+}
 
-// TODO: move to serialize file
-fn has_save_event(mut e: EventReader<SaveSceneEvent>) -> bool {
-    let mut result = false;
-    // iterate over all events to drain
-    for _ in e.iter() {
-        result = true;
-    }
-    result
+pub struct SaveSceneCompleted {
+    pub path: PathBuf,
+}
+
+pub struct SaveSceneFailed {
+    pub path: PathBuf,
+    pub error: std::io::Error,
 }
 
 #[derive(SystemParam)]
 struct SceneParam<'w, 's> {
     enemies: Query<'w, 's, Entity, With<Enemy>>,
+    rails: Query<'w, 's, Entity, With<PlayerRail>>,
+    level_ends: Query<'w, 's, Entity, With<LevelEnd>>,
+    scroll_timelines: Query<'w, 's, Entity, With<ScrollTimeline>>,
+    spawn_triggers: Query<'w, 's, Entity, With<SpawnTrigger>>,
+    checkpoints: Query<'w, 's, Entity, With<Checkpoint>>,
 }
 
 #[derive(Component)]
-struct SaveTask(pub Task<()>);
+struct SaveTask {
+    path: PathBuf,
+    task: Task<std::io::Result<()>>,
+}
 
-fn save_scene(world: &mut World) {
+// the enemies, spawn triggers, rails, scroll timeline, checkpoints and level end of the level
+pub(crate) fn level_scene_ron(world: &mut World) -> Result<String, ron::Error> {
     let mut state = SystemState::<SceneParam>::new(world);
     let scene_params = state.get_mut(world);
-    let enemies = scene_params.enemies.iter().collect();
+    let entities = scene_params
+        .enemies
+        .iter()
+        .chain(scene_params.rails.iter())
+        .chain(scene_params.level_ends.iter())
+        .chain(scene_params.scroll_timelines.iter())
+        .chain(scene_params.spawn_triggers.iter())
+        .chain(scene_params.checkpoints.iter())
+        .collect();
 
     let type_registry = world.get_resource::<AppTypeRegistry>().unwrap();
-    let scene = scene_from_entities(world, type_registry, enemies);
-    let scene = scene.serialize_ron(type_registry).unwrap();
-    let task = Compat::new(async {
-        let result = fs::write("assets/levels/temp.ron", scene).await;
-        if let Err(error) = result {
-            dbg!(error);
-        } else {
-            dbg!("saved");
+    let scene = scene_from_entities(world, type_registry, entities, &SceneFilter::level());
+    scene.serialize_ron(type_registry)
+}
+
+pub(crate) fn deserialize_scene(
+    type_registry: &AppTypeRegistry,
+    contents: &str,
+) -> Result<DynamicScene, Box<dyn Error>> {
+    let mut deserializer = ron::de::Deserializer::from_str(contents)?;
+    let scene = SceneDeserializer {
+        type_registry: &type_registry.read(),
+    }
+    .deserialize(&mut deserializer)?;
+    Ok(scene)
+}
+
+pub(crate) fn spawn_scene(world: &mut World, contents: &str) -> Result<(), Box<dyn Error>> {
+    let type_registry = world.resource::<AppTypeRegistry>().clone();
+    let scene = deserialize_scene(&type_registry, contents)?;
+    scene.write_to_world(world, &mut EntityMap::default())?;
+    Ok(())
+}
+
+/// Rewrites a level file saved before `SceneFilter::level` existed, dropping the runtime-only
+/// components. Every component in the file has to be registered in `type_registry`.
+pub fn migrate_level(
+    type_registry: &AppTypeRegistry,
+    contents: &str,
+) -> Result<String, Box<dyn Error>> {
+    let mut scene = deserialize_scene(type_registry, contents)?;
+    let filter = SceneFilter::level();
+    {
+        let registry = type_registry.read();
+        for entity in &mut scene.entities {
+            entity.components.retain(|component| {
+                match registry.get_with_name(component.type_name()) {
+                    Some(registration) => filter.allows(registration.type_id()),
+                    None => true,
+                }
+            });
         }
-    });
-    dbg!("try save");
-    let task = IoTaskPool::get().spawn(task);
+    }
+    Ok(scene.serialize_ron(type_registry)?)
+}
 
-    world.spawn(SaveTask(task));
+fn save_scene(world: &mut World, mut save_events: Local<ManualEventReader<SaveSceneEvent>>) {
+    let paths: Vec<PathBuf> = save_events
+        .iter(world.resource::<Events<SaveSceneEvent>>())
+        .map(|event| event.path.clone())
+        .collect();
+    if paths.is_empty() {
+        return;
+    }
+
+    let scene = match level_scene_ron(world) {
+        Ok(scene) => scene,
+        Err(error) => {
+            for path in paths {
+                let error = std::io::Error::new(std::io::ErrorKind::InvalidData, error.clone());
+                world.send_event(SaveSceneFailed { path, error });
+            }
+            return;
+        }
+    };
+    for path in paths {
+        let scene = scene.clone();
+        let task_path = path.clone();
+        let task = Compat::new(async move {
+            if let Some(dir) = task_path.parent() {
+                fs::create_dir_all(dir).await?;
+            }
+            fs::write(task_path, scene).await
+        });
+        let task = IoTaskPool::get().spawn(task);
+        world.spawn(SaveTask { path, task });
+    }
 }
 
-fn handle_save_task(mut commands: Commands, mut save_task: Query<(Entity, &mut SaveTask)>) {
-    if let Ok((entity, mut task)) = save_task.get_single_mut() {
-        future::block_on(future::poll_once(&mut task.0));
-        // TODO: this is wrong, only should despawn when task is done.
+fn handle_save_task(
+    mut commands: Commands,
+    mut save_tasks: Query<(Entity, &mut SaveTask)>,
+    mut completed: EventWriter<SaveSceneCompleted>,
+    mut failed: EventWriter<SaveSceneFailed>,
+) {
+    for (entity, mut save_task) in save_tasks.iter_mut() {
+        let result = match future::block_on(future::poll_once(&mut save_task.task)) {
+            Some(result) => result,
+            None => continue,
+        };
         commands.entity(entity).despawn();
+        let path = save_task.path.clone();
+        match result {
+            Ok(()) => {
+                info!("saved scene to {path:?}");
+                completed.send(SaveSceneCompleted { path });
+            }
+            Err(error) => {
+                warn!("failed to save scene to {path:?}: {error}");
+                failed.send(SaveSceneFailed { path, error });
+            }
+        }
+    }
+}
+
+/// Which reflectable components are copied into a scene, by type
+pub enum SceneFilter {
+    /// only these components
+    Allow(HashSet<TypeId>),
+    /// every component but these
+    Deny(HashSet<TypeId>),
+}
+
+impl SceneFilter {
+    /// Only the components levels are authored with, so level files don't pick up anything
+    /// that is computed while the game runs, like `GlobalTransform` or the shapes' asset handles
+    pub fn level() -> Self {
+        SceneFilter::Allow(HashSet::from([
+            TypeId::of::<Transform>(),
+            TypeId::of::<Enemy>(),
+            TypeId::of::<EnemyKind>(),
+            TypeId::of::<SpawnTrigger>(),
+            TypeId::of::<PlayerRail>(),
+            TypeId::of::<FixedOffset>(),
+            TypeId::of::<ScrollTimeline>(),
+            TypeId::of::<Checkpoint>(),
+            TypeId::of::<LevelEnd>(),
+        ]))
+    }
+
+    pub fn allows(&self, type_id: TypeId) -> bool {
+        match self {
+            SceneFilter::Allow(types) => types.contains(&type_id),
+            SceneFilter::Deny(types) => !types.contains(&type_id),
+        }
     }
 }
 
@@ -64,6 +214,7 @@ pub fn scene_from_entities(
     world: &World,
     type_registry: &TypeRegistryArc,
     entities: Vec<Entity>,
+    filter: &SceneFilter,
 ) -> DynamicScene {
     let mut scene = DynamicScene::default();
     let type_registry = type_registry.read();
@@ -89,7 +240,9 @@ pub fn scene_from_entities(
             let reflect_component = world
                 .components()
                 .get_info(component_id)
-                .and_then(|info| type_registry.get(info.type_id().unwrap()))
+                .and_then(|info| info.type_id())
+                .filter(|type_id| filter.allows(*type_id))
+                .and_then(|type_id| type_registry.get(type_id))
                 .and_then(|registration| registration.data::<ReflectComponent>());
             if let Some(reflect_component) = reflect_component {
                 for (i, entity) in archetype
@@ -117,8 +270,12 @@ fn load_scene(
     asset_server: Res<AssetServer>,
     mut scene_spawner: ResMut<SceneSpawner>,
     mut state: ResMut<NextState<GameState>>,
+    level_assets: Res<LevelAssets>,
+    manifests: Res<Assets<LevelManifest>>,
+    current_level: Res<CurrentLevel>,
 ) {
-    let scene_handle = asset_server.load("levels/level1.scn.ron");
+    let manifest = manifests.get(&level_assets.manifest).unwrap();
+    let scene_handle = asset_server.load(manifest.levels[current_level.0].path.as_str());
 
     scene_spawner.spawn_dynamic(scene_handle);
     state.set(GameState::PostLoadLevel);
@@ -127,9 +284,20 @@ fn load_scene(
 pub struct SerializePlugin;
 impl Plugin for SerializePlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(save_scene.run_if(has_save_event))
-            .add_system(load_scene.in_schedule(OnEnter(GameState::LoadLevel)))
+        // Every entity of the level has to be in the world before any of it is prepared for
+        // playing, or preparing the enemies could start the level before the rails are ready.
+        app.configure_set(OnUpdate(GameState::PostLoadLevel).after(scene_spawner_system))
+            .add_system(save_scene.run_if(on_event::<SaveSceneEvent>()))
+            // the editor and checkpoints load levels themselves
+            .add_system(
+                load_scene
+                    .run_if(not(resource_exists::<EditorLevel>()))
+                    .run_if(not(resource_exists::<ReachedCheckpoint>()))
+                    .in_schedule(OnEnter(GameState::LoadLevel)),
+            )
             .add_system(handle_save_task)
-            .add_event::<SaveSceneEvent>();
+            .add_event::<SaveSceneEvent>()
+            .add_event::<SaveSceneCompleted>()
+            .add_event::<SaveSceneFailed>();
     }
 }
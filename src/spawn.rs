@@ -0,0 +1,139 @@
+use bevy::prelude::*;
+
+use crate::editor::EditorLevel;
+use crate::enemy::{build_enemy, Enemy, EnemyArchetypes, EnemyKind};
+use crate::loading::EnemyAssets;
+use crate::physics::{SimulationSet, Velocity};
+use crate::{GameState, LevelEntity};
+
+/// Spawns a wave of enemies once `condition` is met, then despawns itself.
+/// Triggers are checked in order of `order`.
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct SpawnTrigger {
+    /// index of the wave in the level, so "the previous wave" is the one before it
+    pub order: u32,
+    pub condition: SpawnCondition,
+    pub wave: Vec<WaveEnemy>,
+}
+
+#[derive(Default, Reflect, FromReflect, Clone, Copy, Debug, PartialEq)]
+pub enum SpawnCondition {
+    /// the camera scrolled up to this y position
+    CameraY(f32),
+    /// seconds after the previous wave spawned, or after the level started
+    Delay(f32),
+    /// no enemy of the previous wave is left, or right away when there was no previous wave
+    #[default]
+    PreviousWaveCleared,
+}
+
+#[derive(Default, Reflect, FromReflect, Clone, Debug, PartialEq)]
+pub struct WaveEnemy {
+    /// name of the archetype, like `EnemyKind`
+    pub kind: String,
+    /// relative to the camera, so waves can start off-screen
+    pub position: Vec2,
+}
+
+// the last wave spawned in the current level
+#[derive(Resource, Default)]
+struct LastWave {
+    enemies: Vec<Entity>,
+    // seconds since it spawned
+    elapsed: f32,
+}
+
+fn reset_last_wave(mut last_wave: ResMut<LastWave>) {
+    *last_wave = LastWave::default();
+}
+
+fn after_deserialize_spawn_trigger(
+    mut commands: Commands,
+    q: Query<Entity, (With<SpawnTrigger>, Without<LevelEntity>)>,
+    mut state: ResMut<NextState<GameState>>,
+    editor: Option<Res<EditorLevel>>,
+) {
+    if q.is_empty() {
+        return;
+    }
+    // levels can start with nothing but spawn triggers
+    state.set(if editor.is_some() {
+        GameState::Editor
+    } else {
+        GameState::Playing
+    });
+    for entity in q.iter() {
+        commands.entity(entity).insert(LevelEntity);
+    }
+}
+
+fn trigger_waves(
+    mut commands: Commands,
+    triggers: Query<(Entity, &SpawnTrigger)>,
+    enemies: Query<(), With<Enemy>>,
+    camera: Query<&Transform, (With<Camera>, With<Velocity>)>,
+    mut last_wave: ResMut<LastWave>,
+    enemy_assets: Res<EnemyAssets>,
+    archetypes: Res<Assets<EnemyArchetypes>>,
+    fixed_time: Res<FixedTime>,
+) {
+    last_wave.elapsed += fixed_time.period.as_secs_f32();
+    let camera = camera.single().translation;
+    let archetypes = &archetypes.get(&enemy_assets.archetypes).unwrap().archetypes;
+
+    let mut triggers: Vec<(Entity, &SpawnTrigger)> = triggers.iter().collect();
+    triggers.sort_by_key(|(_, trigger)| trigger.order);
+    // enemies spawned this tick aren't in the query yet, so they count as cleared
+    let mut wave_cleared = last_wave.enemies.iter().all(|e| !enemies.contains(*e));
+    for (entity, trigger) in triggers {
+        let ready = match trigger.condition {
+            SpawnCondition::CameraY(y) => camera.y >= y,
+            SpawnCondition::Delay(seconds) => last_wave.elapsed >= seconds,
+            SpawnCondition::PreviousWaveCleared => wave_cleared,
+        };
+        if !ready {
+            continue;
+        }
+
+        let mut wave = Vec::new();
+        for enemy in &trigger.wave {
+            let Some(archetype) = archetypes.get(&enemy.kind) else {
+                warn!("unknown enemy archetype {}", enemy.kind);
+                continue;
+            };
+            let position = camera.truncate() + enemy.position;
+            let mut entity_commands = commands.spawn(EnemyKind(enemy.kind.clone()));
+            build_enemy(
+                &mut entity_commands,
+                Transform::from_translation(position.extend(1.0)),
+                archetype,
+            );
+            wave.push(entity_commands.id());
+        }
+        *last_wave = LastWave {
+            enemies: wave,
+            elapsed: 0.0,
+        };
+        wave_cleared = false;
+        commands.entity(entity).despawn();
+    }
+}
+
+pub struct SpawnPlugin;
+impl Plugin for SpawnPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<SpawnTrigger>()
+            .register_type::<SpawnCondition>()
+            .register_type::<WaveEnemy>()
+            .register_type::<Vec<WaveEnemy>>()
+            .init_resource::<LastWave>()
+            .add_system(reset_last_wave.in_schedule(OnEnter(GameState::LoadLevel)))
+            .add_system(after_deserialize_spawn_trigger.in_set(OnUpdate(GameState::PostLoadLevel)))
+            .add_system(
+                trigger_waves
+                    .in_set(SimulationSet::Gameplay)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            );
+    }
+}
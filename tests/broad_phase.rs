@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+use shoe_shmup::{BroadPhase, CollisionLayer};
+
+fn entity(index: u32) -> Entity {
+    Entity::from_raw(index)
+}
+
+#[test]
+fn query_finds_overlapping_bounds_on_layer() {
+    let mut broad_phase = BroadPhase::new(64.0);
+    broad_phase.insert(
+        entity(0),
+        CollisionLayer::Enemy,
+        Vec2::ZERO,
+        Vec2::splat(10.0),
+    );
+    broad_phase.insert(
+        entity(1),
+        CollisionLayer::Enemy,
+        Vec2::new(200.0, 0.0),
+        Vec2::splat(10.0),
+    );
+    broad_phase.insert(
+        entity(2),
+        CollisionLayer::EnemyBullet,
+        Vec2::ZERO,
+        Vec2::splat(3.0),
+    );
+
+    let found = broad_phase.query(CollisionLayer::Enemy, Vec2::splat(-5.0), Vec2::splat(5.0));
+
+    assert_eq!(found, vec![entity(0)]);
+}
+
+#[test]
+fn candidate_pairs_are_found_once() {
+    let mut broad_phase = BroadPhase::new(64.0);
+    // straddles four cells
+    broad_phase.insert(
+        entity(0),
+        CollisionLayer::Enemy,
+        Vec2::ZERO,
+        Vec2::splat(40.0),
+    );
+    broad_phase.insert(
+        entity(1),
+        CollisionLayer::PlayerBullet,
+        Vec2::new(30.0, 30.0),
+        Vec2::splat(40.0),
+    );
+    broad_phase.insert(
+        entity(2),
+        CollisionLayer::Enemy,
+        Vec2::new(10.0, 0.0),
+        Vec2::splat(40.0),
+    );
+
+    let mut across =
+        broad_phase.candidate_pairs(CollisionLayer::PlayerBullet, CollisionLayer::Enemy);
+    across.sort();
+    assert_eq!(across, vec![(entity(1), entity(0)), (entity(1), entity(2))]);
+
+    let within = broad_phase.candidate_pairs(CollisionLayer::Enemy, CollisionLayer::Enemy);
+    assert_eq!(within.len(), 1);
+}
+
+#[test]
+fn separated_bounds_are_not_candidates() {
+    let mut broad_phase = BroadPhase::new(64.0);
+    // same cell, but the boxes don't touch
+    broad_phase.insert(
+        entity(0),
+        CollisionLayer::Player,
+        Vec2::new(5.0, 5.0),
+        Vec2::splat(4.0),
+    );
+    broad_phase.insert(
+        entity(1),
+        CollisionLayer::Enemy,
+        Vec2::new(50.0, 50.0),
+        Vec2::splat(4.0),
+    );
+
+    assert!(broad_phase
+        .candidate_pairs(CollisionLayer::Player, CollisionLayer::Enemy)
+        .is_empty());
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use common::{camera_y, click_button, headless_app, run_until_state, shoot_player, start_level};
+use shoe_shmup::{
+    Checkpoint, Enemy, GameState, Invulnerable, LevelEntity, Lives, Player, Score, STARTING_LIVES,
+};
+
+const CHECKPOINT_Y: f32 = 10.0;
+
+fn enemies(app: &mut App) -> Vec<Entity> {
+    let mut enemies: Vec<Entity> = app
+        .world
+        .query_filtered::<Entity, With<Enemy>>()
+        .iter(&app.world)
+        .collect();
+    enemies.sort();
+    enemies
+}
+
+// places a checkpoint early in the level and plays until it is reached,
+// keeping the player out of harm's way
+fn set_up_checkpoint(app: &mut App) {
+    app.world.spawn((
+        Checkpoint,
+        Transform::from_xyz(0.0, CHECKPOINT_Y, 0.0),
+        LevelEntity,
+    ));
+    let player = app
+        .world
+        .query_filtered::<Entity, With<Player>>()
+        .single(&app.world);
+    app.world
+        .entity_mut(player)
+        .insert(Invulnerable(Timer::from_seconds(100.0, TimerMode::Once)));
+
+    for _ in 0..1000 {
+        let checkpoints = app
+            .world
+            .query_filtered::<(), With<Checkpoint>>()
+            .iter(&app.world)
+            .count();
+        if checkpoints == 0 {
+            return;
+        }
+        app.update();
+    }
+    panic!("checkpoint not reached");
+}
+
+fn lose_last_life(app: &mut App) {
+    let player = app
+        .world
+        .query_filtered::<Entity, With<Player>>()
+        .single(&app.world);
+    app.world.entity_mut(player).remove::<Invulnerable>();
+    app.world.resource_mut::<Lives>().0 = 1;
+    shoot_player(app);
+    run_until_state(app, GameState::PlayerDead);
+    app.update();
+}
+
+#[test]
+fn continue_restores_the_level_at_the_checkpoint() {
+    let mut app = headless_app();
+    start_level(&mut app);
+    // destroyed before the checkpoint, so it stays destroyed
+    let before = enemies(&mut app)[0];
+    app.world.despawn(before);
+    app.world.resource_mut::<Score>().points = 100;
+    set_up_checkpoint(&mut app);
+
+    let at_checkpoint = enemies(&mut app).len();
+    // destroyed after the checkpoint, so it comes back
+    let after = enemies(&mut app)[0];
+    app.world.despawn(after);
+    app.world.resource_mut::<Score>().points = 300;
+
+    lose_last_life(&mut app);
+    click_button(&mut app);
+    run_until_state(&mut app, GameState::Playing);
+
+    assert!((camera_y(&mut app) - CHECKPOINT_Y).abs() < 1.0);
+    assert_eq!(enemies(&mut app).len(), at_checkpoint);
+    assert_eq!(app.world.resource::<Score>().points, 100);
+    assert_eq!(app.world.resource::<Lives>().0, STARTING_LIVES);
+    assert_eq!(
+        app.world
+            .query_filtered::<(), With<Player>>()
+            .iter(&app.world)
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn escape_gives_up_instead_of_continuing() {
+    let mut app = headless_app();
+    start_level(&mut app);
+    set_up_checkpoint(&mut app);
+    lose_last_life(&mut app);
+
+    app.world.send_event(KeyboardInput {
+        scan_code: 0,
+        key_code: Some(KeyCode::Escape),
+        state: ButtonState::Pressed,
+    });
+    run_until_state(&mut app, GameState::Menu);
+    app.update();
+
+    // the next run starts the level from the beginning
+    start_level(&mut app);
+    assert!(camera_y(&mut app) < CHECKPOINT_Y);
+}
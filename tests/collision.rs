@@ -0,0 +1,163 @@
+mod common;
+
+use bevy::prelude::*;
+use std::f32::consts::FRAC_PI_2;
+
+use common::{headless_app, start_level};
+use impacted::CollisionShape;
+use shoe_shmup::{
+    sweep, Actions, Bullet, BulletOwner, CollisionEvent, Enemy, Lives, Player, SpawnBullet,
+    STARTING_LIVES,
+};
+
+fn square_at(x: f32, y: f32) -> CollisionShape {
+    CollisionShape::new_rectangle(10.0, 10.0).with_transform(GlobalTransform::from_xyz(x, y, 0.0))
+}
+
+#[test]
+fn sweep_finds_earliest_hit() {
+    let bullet = CollisionShape::new_circle(4.0);
+    let end = Transform::from_xyz(0.0, 100.0, 0.0);
+    let far = square_at(0.0, 60.0);
+    let near = square_at(0.0, 30.0);
+
+    let hit = sweep(
+        &bullet,
+        &end,
+        Vec2::new(0.0, 1000.0),
+        0.1,
+        4.0,
+        [("far", &far), ("near", &near)].into_iter(),
+    );
+
+    let (target, fraction) = hit.unwrap();
+    assert_eq!(target, "near");
+    assert!(fraction > 0.15 && fraction < 0.3, "{fraction}");
+}
+
+#[test]
+fn sweep_misses_shapes_off_the_path() {
+    let bullet = CollisionShape::new_circle(4.0);
+    let end = Transform::from_xyz(0.0, 100.0, 0.0);
+    let beside = square_at(20.0, 50.0);
+    let behind = square_at(0.0, -20.0);
+
+    let hit = sweep(
+        &bullet,
+        &end,
+        Vec2::new(0.0, 1000.0),
+        0.1,
+        4.0,
+        [(0, &beside), (1, &behind)].into_iter(),
+    );
+
+    assert!(hit.is_none());
+}
+
+// moves about 100 px in one tick, starting below the lowest enemy and ending past it
+#[test]
+fn fast_bullet_does_not_pass_through_enemy() {
+    let mut app = headless_app();
+    start_level(&mut app);
+
+    let (enemy, transform) = app
+        .world
+        .query_filtered::<(Entity, &Transform), With<Enemy>>()
+        .iter(&app.world)
+        .map(|(e, t)| (e, *t))
+        .min_by(|(_, a), (_, b)| a.translation.y.total_cmp(&b.translation.y))
+        .unwrap();
+    let start = transform.translation - Vec3::new(0.0, 60.0, 0.0);
+    app.world.send_event(SpawnBullet {
+        initial_transform: Transform::from_translation(start),
+        owner: BulletOwner::Player,
+        speed: 6000.0,
+    });
+    app.update();
+    app.update();
+
+    assert!(app.world.get_entity(enemy).is_none());
+}
+
+fn spawn_still_bullet(app: &mut App, owner: BulletOwner, translation: Vec3) {
+    app.world.send_event(SpawnBullet {
+        initial_transform: Transform::from_translation(translation),
+        owner,
+        speed: 0.0,
+    });
+}
+
+#[test]
+fn collision_events_only_between_interacting_layers() {
+    let mut app = headless_app();
+    start_level(&mut app);
+    let (player, player_transform) = app
+        .world
+        .query_filtered::<(Entity, &Transform), With<Player>>()
+        .single(&app.world);
+    let player_translation = player_transform.translation;
+
+    // bullets from both sides pass through each other
+    let beside = player_translation + Vec3::new(60.0, 0.0, 0.0);
+    spawn_still_bullet(&mut app, BulletOwner::Player, beside);
+    spawn_still_bullet(&mut app, BulletOwner::Enemy, beside);
+    spawn_still_bullet(&mut app, BulletOwner::Enemy, player_translation);
+    let mut reader = app.world.resource::<Events<CollisionEvent>>().get_reader();
+    app.update();
+    // before the bullet on the player hits it and is removed
+    let bullets: Vec<(Entity, Vec3)> = app
+        .world
+        .query_filtered::<(Entity, &Transform), With<Bullet>>()
+        .iter(&app.world)
+        .map(|(e, t)| (e, t.translation))
+        .collect();
+    app.update();
+
+    let at = |translation: Vec3| -> Vec<Entity> {
+        bullets
+            .iter()
+            .filter(|(_, t)| *t == translation)
+            .map(|(e, _)| *e)
+            .collect()
+    };
+    let events: Vec<CollisionEvent> = reader
+        .iter(app.world.resource::<Events<CollisionEvent>>())
+        .copied()
+        .collect();
+    let on_player = at(player_translation);
+    assert_eq!(on_player.len(), 1);
+    assert!(events.contains(&CollisionEvent {
+        a: player,
+        b: on_player[0]
+    }));
+    let beside = at(beside);
+    assert_eq!(beside.len(), 2);
+    assert!(events
+        .iter()
+        .all(|event| !beside.contains(&event.a) && !beside.contains(&event.b)));
+}
+
+// turned sideways the player is 12 px wide, wider than it is without turning
+#[test]
+fn turned_player_is_hit_from_the_side() {
+    let mut app = headless_app();
+    start_level(&mut app);
+    app.world.resource_mut::<Actions>().player_stop = true;
+    let player = app
+        .world
+        .query_filtered::<Entity, With<Player>>()
+        .single(&app.world);
+    app.world.get_mut::<Transform>(player).unwrap().rotation = Quat::from_rotation_z(FRAC_PI_2);
+    app.update();
+
+    let translation = app.world.get::<Transform>(player).unwrap().translation;
+    spawn_still_bullet(
+        &mut app,
+        BulletOwner::Enemy,
+        translation + Vec3::new(8.0, 0.0, 0.0),
+    );
+    app.update();
+    app.update();
+
+    assert_eq!(app.world.resource::<Lives>().0, STARTING_LIVES - 1);
+}
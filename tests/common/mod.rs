@@ -0,0 +1,203 @@
+// not every test file uses every helper
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use bevy::ecs::event::ManualEventReader;
+use bevy::log::LogPlugin;
+use bevy::prelude::*;
+use bevy::render::settings::WgpuSettings;
+use bevy::render::RenderPlugin;
+use bevy::time::TimeUpdateStrategy;
+use bevy::ui::UiPlugin;
+use bevy::window::ExitCondition;
+use bevy::winit::WinitPlugin;
+use shoe_shmup::{
+    Actions, BulletOwner, CurrentLevel, GameState, HeadlessGamePlugin, HighScoreFile, InputMapFile,
+    Player, ReplayMode, SaveSceneCompleted, SaveSceneFailed, SpawnBullet,
+};
+
+// upper bound on frames to wait for asynchronous work like scene loading
+const MAX_FRAMES: usize = 1000;
+
+/// A path in the temp directory no other test uses, for files written by the game
+pub fn temp_file(name: &str) -> PathBuf {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let id = NEXT.fetch_add(1, Ordering::Relaxed);
+    let dir = std::env::temp_dir().join(format!("shoe_shmup_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join(format!("{id}_{name}"))
+}
+
+pub fn temp_high_score_file() -> PathBuf {
+    temp_file("high_scores.ron")
+}
+
+/// Builds the game with no window, renderer or audio and runs it until it reaches the menu.
+/// High scores and controls are kept in fresh temporary files.
+pub fn headless_app() -> App {
+    headless_app_with_high_scores(temp_high_score_file())
+}
+
+pub fn headless_app_with_high_scores(high_score_file: PathBuf) -> App {
+    headless_app_with_files(high_score_file, temp_file("input_map.ron"))
+}
+
+pub fn headless_app_with_input_map(input_map_file: PathBuf) -> App {
+    headless_app_with_files(temp_high_score_file(), input_map_file)
+}
+
+fn headless_app_with_files(high_score_file: PathBuf, input_map_file: PathBuf) -> App {
+    let mut app = App::new();
+    app.insert_resource(HighScoreFile(high_score_file));
+    app.insert_resource(InputMapFile(input_map_file));
+    app.add_plugins(
+        DefaultPlugins
+            .set(RenderPlugin {
+                wgpu_settings: WgpuSettings {
+                    backends: None,
+                    ..default()
+                },
+            })
+            .set(WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                close_when_requested: false,
+            })
+            .disable::<WinitPlugin>()
+            // buttons are pressed by writing `Interaction` directly
+            .disable::<UiPlugin>()
+            .disable::<LogPlugin>(),
+    )
+    .add_plugin(HeadlessGamePlugin)
+    .add_system(simulate_frame_time.in_base_set(CoreSet::Last));
+
+    run_until_state(&mut app, GameState::Menu);
+    app
+}
+
+/// Simulated duration of every update. Without it each update lasts one fixed timestep tick.
+#[derive(Resource)]
+pub struct FrameTime(pub Duration);
+
+// Updates take a set amount of time however long the frame really took,
+// so tests don't depend on how fast they run. Replays set the time themselves.
+fn simulate_frame_time(
+    time: Res<Time>,
+    fixed_time: Res<FixedTime>,
+    frame_time: Option<Res<FrameTime>>,
+    replay: Option<Res<ReplayMode>>,
+    mut time_strategy: ResMut<TimeUpdateStrategy>,
+) {
+    if replay.is_some() {
+        return;
+    }
+    let frame_time = frame_time.map_or(fixed_time.period, |frame_time| frame_time.0);
+    if let Some(last_update) = time.last_update() {
+        *time_strategy = TimeUpdateStrategy::ManualInstant(last_update + frame_time);
+    }
+}
+
+pub fn current_state(app: &App) -> GameState {
+    app.world.resource::<State<GameState>>().0.clone()
+}
+
+pub fn set_state(app: &mut App, state: GameState) {
+    app.world.resource_mut::<NextState<GameState>>().set(state);
+}
+
+/// Updates the app until it is in `state`, panicking if that takes too long.
+pub fn run_until_state(app: &mut App, state: GameState) {
+    for _ in 0..MAX_FRAMES {
+        if current_state(app) == state {
+            return;
+        }
+        app.update();
+    }
+    panic!(
+        "expected to reach {state:?}, still in {:?}",
+        current_state(app)
+    );
+}
+
+/// Presses the only button on screen, like the play button in the menu.
+pub fn click_button(app: &mut App) {
+    let mut interaction = app
+        .world
+        .query_filtered::<&mut Interaction, With<Button>>()
+        .single_mut(&mut app.world);
+    *interaction = Interaction::Clicked;
+}
+
+/// Hits the player with a still enemy bullet and waits for the hit to count
+pub fn shoot_player(app: &mut App) {
+    let translation = app
+        .world
+        .query_filtered::<&Transform, With<Player>>()
+        .single(&app.world)
+        .translation;
+    app.world.send_event(SpawnBullet {
+        initial_transform: Transform::from_translation(translation),
+        owner: BulletOwner::Enemy,
+        speed: 0.0,
+    });
+    app.update();
+    app.update();
+}
+
+/// Starts a level from the menu and waits until it is playable.
+pub fn start_level(app: &mut App) {
+    click_button(app);
+    run_until_state(app, GameState::Playing);
+    // let the remaining PostLoadLevel commands and transform propagation settle
+    app.update();
+}
+
+/// Starts level 2, which mixes enemy archetypes and has a scroll timeline.
+pub fn start_level2(app: &mut App) {
+    app.world.resource_mut::<CurrentLevel>().0 = 1;
+    start_level(app);
+}
+
+/// Y of the camera, which scrolls up through the level
+pub fn camera_y(app: &mut App) -> f32 {
+    app.world
+        .query_filtered::<&Transform, With<Camera>>()
+        .single(&app.world)
+        .translation
+        .y
+}
+
+/// Where the player aims, relative to the player
+pub fn aim(app: &mut App) -> Vec2 {
+    let player = app
+        .world
+        .query_filtered::<&Transform, With<Player>>()
+        .single(&app.world)
+        .translation
+        .truncate();
+    app.world.resource::<Actions>().player_point.unwrap() - player
+}
+
+/// Updates the app until a scene save started before the last update finishes,
+/// returning the path saved to or the error.
+pub fn wait_for_save(app: &mut App) -> Result<PathBuf, std::io::Error> {
+    let mut completed = ManualEventReader::<SaveSceneCompleted>::default();
+    let mut failed = ManualEventReader::<SaveSceneFailed>::default();
+    for _ in 0..MAX_FRAMES {
+        app.update();
+        let world = &app.world;
+        if let Some(event) = completed.iter(world.resource()).next() {
+            return Ok(event.path.clone());
+        }
+        if let Some(event) = failed.iter(world.resource()).next() {
+            return Err(std::io::Error::new(
+                event.error.kind(),
+                event.error.to_string(),
+            ));
+        }
+    }
+    panic!("save didn't finish");
+}
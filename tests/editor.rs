@@ -0,0 +1,139 @@
+mod common;
+
+use std::path::Path;
+
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::mouse::MouseButtonInput;
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use common::{headless_app, run_until_state, temp_file, wait_for_save};
+use shoe_shmup::{ChosenLevel, EditorCursor, EditorLevel, Enemy, EnemyKind, GameState};
+
+fn open_editor(path: &Path) -> App {
+    let mut app = headless_app();
+    app.insert_resource(EditorLevel::new(path));
+    run_until_state(&mut app, GameState::Editor);
+    app.update();
+    app
+}
+
+fn enemy_positions(app: &mut App) -> Vec<Vec2> {
+    let mut positions: Vec<Vec2> = app
+        .world
+        .query_filtered::<&Transform, With<Enemy>>()
+        .iter(&app.world)
+        .map(|t| t.translation.truncate())
+        .collect();
+    positions.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    positions
+}
+
+fn mouse(app: &mut App, button: MouseButton, state: ButtonState) {
+    app.world.send_event(MouseButtonInput { button, state });
+    app.update();
+}
+
+fn click_at(app: &mut App, button: MouseButton, position: Vec2) {
+    app.world.resource_mut::<EditorCursor>().0 = Some(position);
+    mouse(app, button, ButtonState::Pressed);
+    mouse(app, button, ButtonState::Released);
+}
+
+fn key(app: &mut App, key_code: KeyCode, state: ButtonState) {
+    app.world.send_event(KeyboardInput {
+        scan_code: 0,
+        key_code: Some(key_code),
+        state,
+    });
+    app.update();
+}
+
+fn save(app: &mut App) {
+    key(app, KeyCode::LControl, ButtonState::Pressed);
+    key(app, KeyCode::S, ButtonState::Pressed);
+    wait_for_save(app).unwrap();
+    key(app, KeyCode::S, ButtonState::Released);
+    key(app, KeyCode::LControl, ButtonState::Released);
+}
+
+#[test]
+fn places_drags_and_deletes_enemies() {
+    let path = temp_file("level.scn.ron");
+    std::fs::copy("assets/levels/level1.scn.ron", &path).unwrap();
+    let mut app = open_editor(&path);
+    let original = enemy_positions(&mut app);
+    assert!(!original.is_empty());
+
+    // placing on an empty spot, snapped to the grid
+    key(&mut app, KeyCode::G, ButtonState::Pressed);
+    click_at(&mut app, MouseButton::Left, Vec2::new(23.0, -148.0));
+    let placed = Vec2::new(20.0, -150.0);
+    assert_eq!(enemy_positions(&mut app).len(), original.len() + 1);
+    assert!(enemy_positions(&mut app).contains(&placed));
+
+    // dragging an existing enemy keeps it at the same offset from the cursor
+    key(&mut app, KeyCode::G, ButtonState::Released);
+    key(&mut app, KeyCode::G, ButtonState::Pressed);
+    assert_eq!(app.world.resource::<EditorLevel>().grid, None);
+    let dragged = original[0];
+    app.world.resource_mut::<EditorCursor>().0 = Some(dragged);
+    mouse(&mut app, MouseButton::Left, ButtonState::Pressed);
+    app.world.resource_mut::<EditorCursor>().0 = Some(dragged + Vec2::new(0.0, 40.0));
+    app.update();
+    mouse(&mut app, MouseButton::Left, ButtonState::Released);
+    let positions = enemy_positions(&mut app);
+    assert!(!positions.contains(&dragged));
+    assert!(positions.contains(&(dragged + Vec2::new(0.0, 40.0))));
+
+    click_at(&mut app, MouseButton::Right, placed);
+    assert_eq!(enemy_positions(&mut app).len(), original.len());
+}
+
+#[test]
+fn saved_level_loads_back() {
+    // doesn't exist yet, so the level starts empty
+    let path = temp_file("new_level.scn.ron");
+    let mut app = open_editor(&path);
+    assert!(enemy_positions(&mut app).is_empty());
+
+    key(&mut app, KeyCode::Tab, ButtonState::Pressed);
+    let kind = app.world.resource::<EditorLevel>().kind.clone();
+    click_at(&mut app, MouseButton::Left, Vec2::new(-50.0, 100.0));
+    click_at(&mut app, MouseButton::Left, Vec2::new(50.0, 200.0));
+    save(&mut app);
+    key(&mut app, KeyCode::Escape, ButtonState::Pressed);
+    run_until_state(&mut app, GameState::Menu);
+    assert!(!app.world.contains_resource::<EditorLevel>());
+    assert!(enemy_positions(&mut app).is_empty());
+
+    let mut app = open_editor(&path);
+    assert_eq!(
+        enemy_positions(&mut app),
+        vec![Vec2::new(-50.0, 100.0), Vec2::new(50.0, 200.0)]
+    );
+    let kinds: Vec<String> = app
+        .world
+        .query::<&EnemyKind>()
+        .iter(&app.world)
+        .map(|kind| kind.0.clone())
+        .collect();
+    assert_eq!(kinds, vec![kind.clone(), kind]);
+}
+
+#[test]
+fn menu_opens_the_chosen_level() {
+    let mut app = headless_app();
+    run_until_state(&mut app, GameState::Menu);
+
+    key(&mut app, KeyCode::Right, ButtonState::Pressed);
+    key(&mut app, KeyCode::Right, ButtonState::Released);
+    assert_eq!(app.world.resource::<ChosenLevel>().0, 1);
+    key(&mut app, KeyCode::E, ButtonState::Pressed);
+    run_until_state(&mut app, GameState::Editor);
+
+    assert_eq!(
+        app.world.resource::<EditorLevel>().path,
+        Path::new("levels/level2.scn.ron")
+    );
+    assert!(!enemy_positions(&mut app).is_empty());
+}
@@ -0,0 +1,169 @@
+mod common;
+
+use bevy::prelude::*;
+use common::{current_state, headless_app, run_until_state, start_level, start_level2};
+use shoe_shmup::{
+    Bullet, BulletOwner, Emitter, Enemy, EnemyKind, GameState, HitPoints, Lives, Player, Score,
+    SpawnBullet, Swept,
+};
+
+fn find_kind(app: &mut App, kind: &str) -> (Entity, Transform) {
+    app.world
+        .query_filtered::<(Entity, &Transform, &EnemyKind), With<Enemy>>()
+        .iter(&app.world)
+        .find(|(_, _, k)| k.0 == kind)
+        .map(|(e, t, _)| (e, *t))
+        .unwrap()
+}
+
+#[test]
+fn archetype_sets_hit_points() {
+    let mut app = headless_app();
+    start_level2(&mut app);
+
+    let (tank, _) = find_kind(&mut app, "tank");
+    assert_eq!(app.world.get::<HitPoints>(tank).unwrap().0, 5);
+    let (weaver, _) = find_kind(&mut app, "weaver");
+    assert_eq!(app.world.get::<HitPoints>(weaver).unwrap().0, 2);
+}
+
+#[test]
+fn bullet_damages_without_destroying() {
+    let mut app = headless_app();
+    start_level2(&mut app);
+
+    let (tank, transform) = find_kind(&mut app, "tank");
+    app.world.send_event(SpawnBullet::player(transform));
+    app.update();
+    app.update();
+
+    assert_eq!(app.world.get::<HitPoints>(tank).unwrap().0, 4);
+}
+
+#[test]
+fn moving_archetypes_leave_spawn_point() {
+    let mut app = headless_app();
+    start_level2(&mut app);
+
+    let (weaver, start) = find_kind(&mut app, "weaver");
+    let (orbiter, orbit_start) = find_kind(&mut app, "orbiter");
+    for _ in 0..10 {
+        app.update();
+    }
+
+    let weaver_now = app.world.get::<Transform>(weaver).unwrap().translation;
+    assert_ne!(weaver_now.x, start.translation.x);
+    assert_eq!(weaver_now.y, start.translation.y);
+    let orbiter_now = app.world.get::<Transform>(orbiter).unwrap().translation;
+    assert_ne!(orbiter_now, orbit_start.translation);
+}
+
+fn enemy_bullet(transform: Transform) -> SpawnBullet {
+    SpawnBullet {
+        initial_transform: transform,
+        owner: BulletOwner::Enemy,
+        speed: 100.0,
+    }
+}
+
+#[test]
+fn enemy_bullets_do_not_hit_enemies() {
+    let mut app = headless_app();
+    start_level2(&mut app);
+
+    let (tank, transform) = find_kind(&mut app, "tank");
+    app.world.send_event(enemy_bullet(transform));
+    app.update();
+    app.update();
+
+    assert_eq!(app.world.get::<HitPoints>(tank).unwrap().0, 5);
+}
+
+// without sweeping, a bullet touches every enemy it overlaps in the same tick
+#[test]
+fn bullet_only_hits_one_of_overlapping_enemies() {
+    let mut app = headless_app();
+    start_level(&mut app);
+    let enemies: Vec<Entity> = app
+        .world
+        .query_filtered::<Entity, With<Enemy>>()
+        .iter(&app.world)
+        .take(2)
+        .collect();
+    let translation = app.world.get::<Transform>(enemies[0]).unwrap().translation;
+    app.world
+        .get_mut::<Transform>(enemies[1])
+        .unwrap()
+        .translation = translation + Vec3::new(10.0, 0.0, 0.0);
+    for &enemy in &enemies {
+        app.world.get_mut::<HitPoints>(enemy).unwrap().0 = 1;
+    }
+
+    app.world.send_event(SpawnBullet {
+        initial_transform: Transform::from_translation(translation + Vec3::new(5.0, 0.0, 0.0)),
+        owner: BulletOwner::Player,
+        speed: 0.0,
+    });
+    app.update();
+    let bullet = app
+        .world
+        .query_filtered::<Entity, With<Bullet>>()
+        .single(&app.world);
+    app.world.entity_mut(bullet).remove::<Swept>();
+    app.update();
+
+    let destroyed = enemies
+        .iter()
+        .filter(|&&enemy| app.world.get_entity(enemy).is_none())
+        .count();
+    assert_eq!(destroyed, 1);
+    assert_eq!(app.world.resource::<Score>().combo, 1);
+}
+
+#[test]
+fn enemy_bullet_kills_player() {
+    let mut app = headless_app();
+    start_level2(&mut app);
+    app.world.resource_mut::<Lives>().0 = 1;
+
+    let player = *app
+        .world
+        .query_filtered::<&Transform, With<Player>>()
+        .single(&app.world);
+    app.world.send_event(enemy_bullet(player));
+    // spawned in one frame, hits in the next frame's tick and changes state in the one after
+    for _ in 0..3 {
+        app.update();
+    }
+
+    assert_ne!(current_state(&app), GameState::Playing);
+    run_until_state(&mut app, GameState::Menu);
+}
+
+#[test]
+fn emitters_fire_expected_directions() {
+    let aim = Vec2::new(1.0, -1.0).normalize();
+
+    assert_eq!(Emitter::Aimed.directions(aim, 0), vec![aim]);
+
+    let radial = Emitter::Radial { count: 4 }.directions(aim, 0);
+    assert_eq!(radial.len(), 4);
+    assert!(radial[0].abs_diff_eq(Vec2::NEG_Y, 1e-5));
+    assert!(radial[1].abs_diff_eq(Vec2::X, 1e-5));
+
+    let spiral = Emitter::Spiral {
+        arms: 2,
+        turn: 90.0,
+    };
+    assert!(spiral.directions(aim, 0)[0].abs_diff_eq(Vec2::NEG_Y, 1e-5));
+    assert!(spiral.directions(aim, 1)[0].abs_diff_eq(Vec2::X, 1e-5));
+
+    let spread = Emitter::Spread {
+        count: 3,
+        angle: 90.0,
+    }
+    .directions(aim, 0);
+    assert_eq!(spread.len(), 3);
+    assert!(spread[1].abs_diff_eq(aim, 1e-5));
+    assert!((spread[0].angle_between(spread[2]).to_degrees() - 90.0).abs() < 1e-3);
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use bevy::prelude::*;
+use common::{aim, headless_app, start_level};
+use shoe_shmup::{ActionsPlugin, ActiveGamepad};
+
+fn set_right_stick(app: &mut App, gamepad: Gamepad, x: f32, y: f32) {
+    let mut axes = app.world.resource_mut::<Axis<GamepadAxis>>();
+    axes.set(GamepadAxis::new(gamepad, GamepadAxisType::RightStickX), x);
+    axes.set(GamepadAxis::new(gamepad, GamepadAxisType::RightStickY), y);
+}
+
+#[test]
+fn released_stick_keeps_aiming_in_the_same_direction() {
+    let mut app = headless_app();
+    app.add_plugin(ActionsPlugin);
+    start_level(&mut app);
+    let gamepad = Gamepad::new(0);
+    app.world.resource_mut::<ActiveGamepad>().0 = Some(gamepad);
+
+    set_right_stick(&mut app, gamepad, 0.8, 0.0);
+    app.update();
+    assert!(aim(&mut app).abs_diff_eq(Vec2::X, 1e-4));
+
+    // the player moves along the rail meanwhile
+    set_right_stick(&mut app, gamepad, 0.1, 0.0);
+    for _ in 0..30 {
+        app.update();
+    }
+    assert!(
+        aim(&mut app).abs_diff_eq(Vec2::X, 1e-4),
+        "{}",
+        aim(&mut app)
+    );
+}
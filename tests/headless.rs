@@ -0,0 +1,94 @@
+mod common;
+
+use bevy::prelude::*;
+use common::{current_state, headless_app, run_until_state, set_state, start_level};
+use impacted::CollisionShape;
+use shoe_shmup::{Bullet, Enemy, GameState, LevelEntity, Lives, Player, SpawnBullet};
+
+const LEVEL1_ENEMIES: usize = 35;
+
+fn count<F: bevy::ecs::query::ReadOnlyWorldQuery>(app: &mut App) -> usize {
+    app.world
+        .query_filtered::<Entity, F>()
+        .iter(&app.world)
+        .count()
+}
+
+#[test]
+fn starts_in_menu() {
+    let app = headless_app();
+    assert_eq!(current_state(&app), GameState::Menu);
+}
+
+#[test]
+fn loads_level_into_playing() {
+    let mut app = headless_app();
+    start_level(&mut app);
+
+    assert_eq!(current_state(&app), GameState::Playing);
+    assert_eq!(count::<With<Player>>(&mut app), 1);
+    assert_eq!(
+        count::<(With<Enemy>, With<CollisionShape>, With<LevelEntity>)>(&mut app),
+        LEVEL1_ENEMIES
+    );
+}
+
+#[test]
+fn bullet_destroys_enemy() {
+    let mut app = headless_app();
+    start_level(&mut app);
+
+    let (enemy, transform) = app
+        .world
+        .query_filtered::<(Entity, &Transform), With<Enemy>>()
+        .iter(&app.world)
+        .map(|(e, t)| (e, *t))
+        .next()
+        .unwrap();
+    app.world.send_event(SpawnBullet::player(transform));
+    app.update();
+    app.update();
+
+    assert!(app.world.get_entity(enemy).is_none());
+    assert_eq!(count::<With<Enemy>>(&mut app), LEVEL1_ENEMIES - 1);
+    assert_eq!(count::<With<Bullet>>(&mut app), 0);
+}
+
+#[test]
+fn enemy_touching_player_on_last_life_ends_level() {
+    let mut app = headless_app();
+    start_level(&mut app);
+    app.world.resource_mut::<Lives>().0 = 1;
+
+    let player_translation = app
+        .world
+        .query_filtered::<&Transform, With<Player>>()
+        .single(&app.world)
+        .translation;
+    let mut enemy = app
+        .world
+        .query_filtered::<&mut Transform, With<Enemy>>()
+        .iter_mut(&mut app.world)
+        .next()
+        .unwrap();
+    enemy.translation = player_translation;
+
+    run_until_state(&mut app, GameState::Menu);
+    app.update();
+
+    assert_eq!(count::<With<Player>>(&mut app), 0);
+    assert_eq!(count::<With<LevelEntity>>(&mut app), 0);
+}
+
+#[test]
+fn level_can_be_replayed_after_death() {
+    let mut app = headless_app();
+    start_level(&mut app);
+    set_state(&mut app, GameState::PlayerDead);
+    run_until_state(&mut app, GameState::Menu);
+    app.update();
+
+    start_level(&mut app);
+    assert_eq!(count::<With<Player>>(&mut app), 1);
+    assert_eq!(count::<With<Enemy>>(&mut app), LEVEL1_ENEMIES);
+}
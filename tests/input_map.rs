@@ -0,0 +1,260 @@
+mod common;
+
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use bevy::window::{PrimaryWindow, WindowCreated};
+use common::{
+    aim, headless_app, headless_app_with_input_map, run_until_state, start_level, temp_file,
+    FrameTime,
+};
+use shoe_shmup::{
+    Actions, ActionsPlugin, AimMode, Binding, GameState, InputAction, InputMap, InputMapFile,
+};
+
+fn send_key(app: &mut App, key_code: KeyCode, state: ButtonState) {
+    app.world.send_event(KeyboardInput {
+        scan_code: 0,
+        key_code: Some(key_code),
+        state,
+    });
+}
+
+fn tap(app: &mut App, key_code: KeyCode) {
+    for state in [ButtonState::Pressed, ButtonState::Released] {
+        send_key(app, key_code, state);
+        app.update();
+    }
+}
+
+// a level played with input devices, aiming in `aim_mode`
+fn keyboard_aim_app(aim_mode: AimMode) -> App {
+    let mut app = headless_app();
+    app.add_plugin(ActionsPlugin);
+    start_level(&mut app);
+    app.world.resource_mut::<InputMap>().aim_mode = aim_mode;
+    app.update();
+    app
+}
+
+#[test]
+fn default_bindings_match_the_original_controls() {
+    let input_map = InputMap::default();
+    assert_eq!(
+        input_map.action(Binding::Key(KeyCode::F)),
+        Some(InputAction::Stop)
+    );
+    assert_eq!(
+        input_map.action(Binding::Mouse(MouseButton::Left)),
+        Some(InputAction::Shoot)
+    );
+    assert_eq!(
+        input_map.action(Binding::Key(KeyCode::Up)),
+        Some(InputAction::SteerUp)
+    );
+    for action in InputAction::ALL {
+        assert!(!input_map.bindings(action).is_empty(), "{action:?}");
+    }
+}
+
+#[test]
+fn binding_taken_by_another_action_conflicts() {
+    let mut input_map = InputMap::default();
+    assert_eq!(
+        input_map.bind(InputAction::Shoot, Binding::Key(KeyCode::F)),
+        Err(InputAction::Stop)
+    );
+    assert_eq!(
+        input_map.bind(InputAction::Stop, Binding::Key(KeyCode::F)),
+        Ok(())
+    );
+    assert_eq!(
+        input_map.bindings(InputAction::Stop),
+        InputMap::default().bindings(InputAction::Stop)
+    );
+
+    input_map.clear(InputAction::Shoot);
+    assert_eq!(
+        input_map.bind(InputAction::Shoot, Binding::Key(KeyCode::J)),
+        Ok(())
+    );
+    assert_eq!(
+        input_map.bindings(InputAction::Shoot),
+        [Binding::Key(KeyCode::J)]
+    );
+}
+
+#[test]
+fn controls_screen_rebinds_and_saves() {
+    let mut app = headless_app();
+    tap(&mut app, KeyCode::C);
+    run_until_state(&mut app, GameState::Controls);
+
+    // Shoot is second in the list
+    tap(&mut app, KeyCode::Down);
+    tap(&mut app, KeyCode::Back);
+    assert!(app
+        .world
+        .resource::<InputMap>()
+        .bindings(InputAction::Shoot)
+        .is_empty());
+
+    tap(&mut app, KeyCode::Return);
+    tap(&mut app, KeyCode::J);
+    assert_eq!(
+        app.world
+            .resource::<InputMap>()
+            .bindings(InputAction::Shoot),
+        [Binding::Key(KeyCode::J)]
+    );
+
+    // F already stops, so it isn't bound
+    tap(&mut app, KeyCode::Return);
+    tap(&mut app, KeyCode::F);
+    assert_eq!(
+        app.world
+            .resource::<InputMap>()
+            .bindings(InputAction::Shoot),
+        [Binding::Key(KeyCode::J)]
+    );
+
+    tap(&mut app, KeyCode::Escape);
+    run_until_state(&mut app, GameState::Menu);
+
+    let path = app.world.resource::<InputMapFile>().0.clone();
+    let saved: InputMap = ron::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    assert_eq!(&saved, app.world.resource::<InputMap>());
+}
+
+#[test]
+fn controls_screen_switches_aim_mode() {
+    let mut app = headless_app();
+    tap(&mut app, KeyCode::C);
+    run_until_state(&mut app, GameState::Controls);
+
+    tap(&mut app, KeyCode::M);
+    tap(&mut app, KeyCode::Minus);
+    let input_map = app.world.resource::<InputMap>();
+    assert_eq!(input_map.aim_mode, AimMode::KeyboardRotate);
+    assert_eq!(input_map.aim_rotation_speed, 150.0);
+
+    let path = app.world.resource::<InputMapFile>().0.clone();
+    let app = headless_app_with_input_map(path);
+    let input_map = app.world.resource::<InputMap>();
+    assert_eq!(input_map.aim_mode, AimMode::KeyboardRotate);
+    assert_eq!(input_map.aim_rotation_speed, 150.0);
+}
+
+#[test]
+fn old_files_get_default_bindings_for_new_actions() {
+    // saved before aiming could be done with keys, with Q rebound to shoot
+    let path = temp_file("input_map.ron");
+    std::fs::write(
+        &path,
+        "(bindings: {Stop: [Key(F)], Shoot: [Key(Q)], SteerUp: [], SteerDown: [], \
+         SteerLeft: [], SteerRight: []})",
+    )
+    .unwrap();
+
+    let app = headless_app_with_input_map(path);
+    let input_map = app.world.resource::<InputMap>();
+    assert_eq!(
+        input_map.bindings(InputAction::Shoot),
+        [Binding::Key(KeyCode::Q)]
+    );
+    assert!(input_map.bindings(InputAction::SteerUp).is_empty());
+    assert!(input_map.bindings(InputAction::AimLeft).is_empty());
+    assert_eq!(
+        input_map.bindings(InputAction::AimRight),
+        [Binding::Key(KeyCode::E)]
+    );
+    assert_eq!(input_map.aim_mode, AimMode::Pointer);
+}
+
+#[test]
+fn eight_way_aim_steps_once_per_tap() {
+    let mut app = keyboard_aim_app(AimMode::KeyboardEightWay);
+    assert!(aim(&mut app).abs_diff_eq(Vec2::Y, 1e-4));
+
+    tap(&mut app, KeyCode::Q);
+    let up_left = Vec2::new(-1.0, 1.0).normalize();
+    assert!(
+        aim(&mut app).abs_diff_eq(up_left, 1e-4),
+        "{}",
+        aim(&mut app)
+    );
+
+    // holding the key doesn't step any further
+    send_key(&mut app, KeyCode::E, ButtonState::Pressed);
+    for _ in 0..10 {
+        app.update();
+    }
+    assert!(
+        aim(&mut app).abs_diff_eq(Vec2::Y, 1e-4),
+        "{}",
+        aim(&mut app)
+    );
+}
+
+#[test]
+fn rotating_aim_turns_at_the_rotation_speed() {
+    let mut app = keyboard_aim_app(AimMode::KeyboardRotate);
+    app.world.resource_mut::<InputMap>().aim_rotation_speed = 90.0;
+    app.insert_resource(FrameTime(std::time::Duration::from_millis(100)));
+    app.update();
+
+    // half a second at 90 degrees per second
+    send_key(&mut app, KeyCode::Q, ButtonState::Pressed);
+    for _ in 0..5 {
+        app.update();
+    }
+    let up_left = Vec2::new(-1.0, 1.0).normalize();
+    assert!(
+        aim(&mut app).abs_diff_eq(up_left, 1e-4),
+        "{}",
+        aim(&mut app)
+    );
+
+    send_key(&mut app, KeyCode::Q, ButtonState::Released);
+    for _ in 0..5 {
+        app.update();
+    }
+    assert!(
+        aim(&mut app).abs_diff_eq(up_left, 1e-4),
+        "{}",
+        aim(&mut app)
+    );
+}
+
+#[test]
+fn keyboard_aim_ignores_the_cursor() {
+    let mut app = keyboard_aim_app(AimMode::KeyboardRotate);
+    let window = app.world.spawn((Window::default(), PrimaryWindow)).id();
+    // the camera only takes on the size of windows it is told about
+    app.world.send_event(WindowCreated { window });
+    let move_cursor = |app: &mut App| {
+        let position = Vec2::new(100.0, 100.0);
+        app.world
+            .get_mut::<Window>(window)
+            .unwrap()
+            .set_cursor_position(Some(position));
+        app.world.send_event(CursorMoved { window, position });
+        app.update();
+    };
+
+    for aim_mode in [AimMode::KeyboardRotate, AimMode::KeyboardEightWay] {
+        app.world.resource_mut::<InputMap>().aim_mode = aim_mode;
+        move_cursor(&mut app);
+        assert!(
+            aim(&mut app).abs_diff_eq(Vec2::Y, 1e-4),
+            "{aim_mode:?}: {}",
+            aim(&mut app)
+        );
+    }
+
+    // the same movement aims with the pointer
+    app.world.resource_mut::<InputMap>().aim_mode = AimMode::Pointer;
+    let point = app.world.resource::<Actions>().player_point;
+    move_cursor(&mut app);
+    assert_ne!(app.world.resource::<Actions>().player_point, point);
+}
@@ -0,0 +1,209 @@
+mod common;
+
+use bevy::prelude::*;
+use common::headless_app;
+use shoe_shmup::{check_level, EnemyArchetypes, LevelProblem};
+
+fn check(contents: &str) -> Vec<LevelProblem> {
+    let app = headless_app();
+    let archetypes: EnemyArchetypes =
+        ron::from_str(&std::fs::read_to_string("assets/enemies/archetypes.enemies.ron").unwrap())
+            .unwrap();
+    check_level(
+        app.world.resource::<AppTypeRegistry>(),
+        &archetypes,
+        contents,
+    )
+}
+
+fn enemy(id: u32, x: f32, y: f32) -> String {
+    format!(
+        r#"{id}: (
+      components: {{
+        "shoe_shmup::enemy::Enemy": (),
+        "bevy_transform::components::transform::Transform": (
+          translation: (x: {x}, y: {y}, z: 1.0),
+          rotation: (0.0, 0.0, 0.0, 1.0),
+          scale: (x: 1.0, y: 1.0, z: 1.0),
+        ),
+      }},
+    ),"#
+    )
+}
+
+// an open rail through `nodes` with `branches` written like "(from: 0, to: 1)"
+fn rail(id: u32, nodes: &[(f32, f32)], branches: &[&str]) -> String {
+    let nodes: Vec<String> = nodes
+        .iter()
+        .map(|(x, y)| format!("(x: {x}, y: {y})"))
+        .collect();
+    format!(
+        r#"{id}: (
+      components: {{
+        "shoe_shmup::player_rail::PlayerRail": (
+          rail: [{}],
+          closed: false,
+          branches: [{}],
+        ),
+        "shoe_shmup::physics::FixedOffset": ((x: 0.0, y: -220.0)),
+      }},
+    ),"#,
+        nodes.join(", "),
+        branches.join(", "),
+    )
+}
+
+fn straight_rail(id: u32) -> String {
+    rail(id, &[(-110.0, 0.0), (110.0, 0.0)], &[])
+}
+
+fn level(entities: &[String]) -> String {
+    format!("(entities: {{ {} }})", entities.concat())
+}
+
+#[test]
+fn shipped_levels_have_no_problems() {
+    for file in [
+        "assets/levels/level1.scn.ron",
+        "assets/levels/level2.scn.ron",
+    ] {
+        let problems = check(&std::fs::read_to_string(file).unwrap());
+        assert!(problems.is_empty(), "{file}: {problems:?}");
+    }
+}
+
+#[test]
+fn reports_every_unknown_type() {
+    let contents = level(&[
+        r#"0: (components: { "shoe_shmup::enemy::Enemy": (), "shoe_shmup::Missing": () }),"#
+            .to_string(),
+        r#"1: (components: { "shoe_shmup::AlsoMissing": (1.0) }),"#.to_string(),
+    ]);
+    assert_eq!(
+        check(&contents),
+        vec![
+            LevelProblem::UnknownType {
+                entity: 0,
+                type_name: "shoe_shmup::Missing".to_string(),
+            },
+            LevelProblem::UnknownType {
+                entity: 1,
+                type_name: "shoe_shmup::AlsoMissing".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn reports_misplaced_enemies() {
+    let contents = level(&[
+        enemy(0, 0.0, 100.0),
+        enemy(1, 10.0, 110.0),
+        enemy(2, 400.0, 100.0),
+        r#"3: (components: { "shoe_shmup::enemy::Enemy": () }),"#.to_string(),
+        straight_rail(4),
+    ]);
+    assert_eq!(
+        check(&contents),
+        vec![
+            LevelProblem::MissingComponent {
+                entity: 3,
+                component: "Transform",
+                needed_by: "Enemy",
+            },
+            LevelProblem::Overlapping { a: 0, b: 1 },
+            LevelProblem::OutsidePlayfield {
+                entity: 2,
+                x: 400.0,
+            },
+        ]
+    );
+}
+
+#[test]
+fn reports_unsorted_scroll_timeline() {
+    let contents = level(&[
+        enemy(0, 0.0, 100.0),
+        r#"1: (components: { "shoe_shmup::scroll::ScrollTimeline": (keyframes: [
+            (y: 200.0, speed: 10.0, pause: None),
+            (y: 100.0, speed: 20.0, pause: Some(UntilCleared)),
+        ]) }),"#
+            .to_string(),
+        straight_rail(2),
+    ]);
+    assert_eq!(
+        check(&contents),
+        vec![LevelProblem::UnsortedScrollTimeline { entity: 1 }]
+    );
+}
+
+#[test]
+fn reports_unknown_archetypes_in_waves() {
+    let contents = level(&[
+        r#"0: (components: { "shoe_shmup::spawn::SpawnTrigger": (
+        order: 0,
+        condition: CameraY(100.0),
+        wave: [(kind: "grunt", position: (x: 0.0, y: 300.0)), (kind: "nobody", position: (x: 0.0, y: 340.0))],
+    ) }),"#
+            .to_string(),
+        straight_rail(1),
+    ]);
+    assert_eq!(
+        check(&contents),
+        vec![LevelProblem::UnknownArchetype {
+            entity: 0,
+            kind: "nobody".to_string(),
+        }]
+    );
+}
+
+#[test]
+fn reports_levels_without_enemies() {
+    assert_eq!(
+        check(&level(&[straight_rail(0)])),
+        vec![LevelProblem::NoEnemies]
+    );
+    assert!(matches!(
+        check("not a level")[..],
+        [LevelProblem::Invalid(_)]
+    ));
+}
+
+#[test]
+fn reports_levels_without_rail() {
+    assert_eq!(
+        check(&level(&[enemy(0, 0.0, 100.0)])),
+        vec![LevelProblem::NoRail]
+    );
+}
+
+#[test]
+fn reports_rails_too_short_to_move_along() {
+    let contents = level(&[enemy(0, 0.0, 100.0), rail(1, &[(0.0, 0.0)], &[])]);
+    assert_eq!(
+        check(&contents),
+        vec![LevelProblem::RailTooShort {
+            entity: 1,
+            nodes: 1
+        }]
+    );
+}
+
+#[test]
+fn reports_rail_branches_to_missing_nodes() {
+    let contents = level(&[
+        enemy(0, 0.0, 100.0),
+        rail(
+            1,
+            &[(-110.0, 0.0), (0.0, 0.0), (110.0, 0.0)],
+            &["(from: 1, to: 2)", "(from: 1, to: 3)"],
+        ),
+    ]);
+    assert_eq!(
+        check(&contents),
+        vec![LevelProblem::RailBranchOutOfRange {
+            entity: 1,
+            branch: 1
+        }]
+    );
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use bevy::prelude::*;
+use common::{click_button, current_state, headless_app, run_until_state, start_level};
+use shoe_shmup::{CurrentLevel, Enemy, GameState, LevelEnd, LevelEntity, Player, PlayerRail};
+
+const LEVEL2_ENEMIES: usize = 10;
+
+fn despawn_enemies(app: &mut App) {
+    let enemies: Vec<Entity> = app
+        .world
+        .query_filtered::<Entity, With<Enemy>>()
+        .iter(&app.world)
+        .collect();
+    for enemy in enemies {
+        app.world.despawn(enemy);
+    }
+}
+
+#[test]
+fn clearing_enemies_finishes_level() {
+    let mut app = headless_app();
+    start_level(&mut app);
+    despawn_enemies(&mut app);
+
+    app.update();
+    app.update();
+    assert_eq!(current_state(&app), GameState::LevelFinished);
+}
+
+#[test]
+fn reaching_level_end_finishes_level() {
+    let mut app = headless_app();
+    start_level(&mut app);
+
+    let end_y = app
+        .world
+        .query_filtered::<&Transform, With<LevelEnd>>()
+        .single(&app.world)
+        .translation
+        .y;
+    let mut camera = app
+        .world
+        .query_filtered::<&mut Transform, With<Camera>>()
+        .single_mut(&mut app.world);
+    camera.translation.y = end_y;
+
+    app.update();
+    app.update();
+    assert_eq!(current_state(&app), GameState::LevelFinished);
+}
+
+#[test]
+fn next_level_is_loaded_after_results() {
+    let mut app = headless_app();
+    start_level(&mut app);
+    despawn_enemies(&mut app);
+    run_until_state(&mut app, GameState::LevelFinished);
+    app.update();
+
+    click_button(&mut app);
+    run_until_state(&mut app, GameState::Playing);
+    app.update();
+
+    assert_eq!(app.world.resource::<CurrentLevel>().0, 1);
+    let enemies = app
+        .world
+        .query_filtered::<Entity, With<Enemy>>()
+        .iter(&app.world)
+        .count();
+    assert_eq!(enemies, LEVEL2_ENEMIES);
+    let players = app
+        .world
+        .query_filtered::<Entity, With<Player>>()
+        .iter(&app.world)
+        .count();
+    assert_eq!(players, 1);
+}
+
+#[test]
+fn finishing_last_level_returns_to_menu() {
+    let mut app = headless_app();
+    start_level(&mut app);
+    app.world.resource_mut::<CurrentLevel>().0 = 1;
+    despawn_enemies(&mut app);
+    run_until_state(&mut app, GameState::LevelFinished);
+    app.update();
+
+    click_button(&mut app);
+    run_until_state(&mut app, GameState::Menu);
+    app.update();
+
+    assert_eq!(app.world.resource::<CurrentLevel>().0, 0);
+    let level_entities = app
+        .world
+        .query_filtered::<Entity, With<LevelEntity>>()
+        .iter(&app.world)
+        .count();
+    assert_eq!(level_entities, 0);
+}
+
+fn rail_points(app: &mut App) -> Vec<Vec2> {
+    app.world
+        .query::<&PlayerRail>()
+        .single(&app.world)
+        .rail
+        .clone()
+}
+
+#[test]
+fn rail_is_loaded_from_level_file() {
+    let mut app = headless_app();
+    start_level(&mut app);
+    assert_eq!(
+        rail_points(&mut app),
+        vec![Vec2::new(-110.0, 0.0), Vec2::new(110.0, 0.0)]
+    );
+
+    despawn_enemies(&mut app);
+    run_until_state(&mut app, GameState::LevelFinished);
+    app.update();
+    click_button(&mut app);
+    run_until_state(&mut app, GameState::Playing);
+    app.update();
+
+    assert_eq!(
+        rail_points(&mut app),
+        vec![
+            Vec2::new(-110.0, 20.0),
+            Vec2::new(0.0, -20.0),
+            Vec2::new(110.0, 20.0)
+        ]
+    );
+}
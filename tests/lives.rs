@@ -0,0 +1,76 @@
+mod common;
+
+use bevy::prelude::*;
+use common::{headless_app, run_until_state, shoot_player, start_level};
+use shoe_shmup::{
+    Bullet, GameState, Invulnerable, Lives, Player, RailPosition, INVULNERABLE_SECONDS,
+    STARTING_LIVES,
+};
+
+#[test]
+fn hit_costs_a_life_and_respawns_invulnerable() {
+    let mut app = headless_app();
+    start_level(&mut app);
+
+    shoot_player(&mut app);
+    assert_eq!(app.world.resource::<Lives>().0, STARTING_LIVES - 1);
+    assert_eq!(
+        app.world.resource::<State<GameState>>().0,
+        GameState::Playing
+    );
+    // blinking starts with the next tick
+    app.update();
+    let (rail_position, visibility) = app
+        .world
+        .query_filtered::<(&RailPosition, &Visibility), (With<Player>, With<Invulnerable>)>()
+        .single(&app.world);
+    assert_eq!(rail_position.index, 0);
+    assert_eq!(*visibility, Visibility::Hidden);
+
+    // hits while invulnerable don't count
+    shoot_player(&mut app);
+    assert_eq!(app.world.resource::<Lives>().0, STARTING_LIVES - 1);
+
+    let period = app.world.resource::<FixedTime>().period.as_secs_f32();
+    for _ in 0..(INVULNERABLE_SECONDS / period).ceil() as usize {
+        app.update();
+    }
+    let player = app
+        .world
+        .query_filtered::<Entity, (With<Player>, Without<Invulnerable>)>()
+        .get_single(&app.world);
+    assert!(player.is_ok());
+}
+
+#[test]
+fn bullet_that_costs_a_life_is_removed() {
+    let mut app = headless_app();
+    start_level(&mut app);
+
+    shoot_player(&mut app);
+    assert_eq!(app.world.resource::<Lives>().0, STARTING_LIVES - 1);
+    assert_eq!(
+        app.world
+            .query_filtered::<(), With<Bullet>>()
+            .iter(&app.world)
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn losing_the_last_life_ends_the_run() {
+    let mut app = headless_app();
+    start_level(&mut app);
+    app.world.resource_mut::<Lives>().0 = 1;
+
+    shoot_player(&mut app);
+    run_until_state(&mut app, GameState::Menu);
+    assert!(app
+        .world
+        .query_filtered::<(), With<Player>>()
+        .iter(&app.world)
+        .next()
+        .is_none());
+    assert_eq!(app.world.resource::<Lives>().0, STARTING_LIVES);
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy::window::{PrimaryWindow, WindowResolution};
+use common::{headless_app, run_until_state, temp_file};
+use shoe_shmup::{
+    EditorCursor, EditorLevel, GameState, PlayfieldPlugin, SCREEN_HEIGHT, SCREEN_WIDTH,
+};
+
+// the editor on a new level, in a window of `resolution`
+fn editor_in_window(resolution: WindowResolution) -> App {
+    let mut app = headless_app();
+    app.add_plugin(PlayfieldPlugin);
+    app.world.spawn((
+        Window {
+            resolution,
+            ..default()
+        },
+        PrimaryWindow,
+    ));
+    app.insert_resource(EditorLevel::new(temp_file("level.scn.ron")));
+    run_until_state(&mut app, GameState::Editor);
+    // the viewport is fitted in one update and the camera computes its size in the next
+    app.update();
+    app.update();
+    app
+}
+
+// physical position and size of the playfield in the window
+fn viewport(app: &mut App) -> (UVec2, UVec2) {
+    let camera = app.world.query::<&Camera>().single(&app.world);
+    let viewport = camera.viewport.as_ref().unwrap();
+    (viewport.physical_position, viewport.physical_size)
+}
+
+fn camera_position(app: &mut App) -> Vec2 {
+    app.world
+        .query_filtered::<&GlobalTransform, With<Camera>>()
+        .single(&app.world)
+        .translation()
+        .truncate()
+}
+
+// moves the cursor to `position`, in logical pixels from the bottom left of the window
+fn assert_cursor_at(app: &mut App, position: Vec2, expected: Vec2) {
+    app.world
+        .query_filtered::<&mut Window, With<PrimaryWindow>>()
+        .single_mut(&mut app.world)
+        .set_cursor_position(Some(position));
+    app.update();
+    let cursor = app.world.resource::<EditorCursor>().0;
+    assert!(
+        cursor.is_some_and(|cursor| cursor.abs_diff_eq(expected, 1e-3)),
+        "cursor at {position} is at {cursor:?} in the world, expected {expected}"
+    );
+}
+
+const HALF_SCREEN: Vec2 = Vec2::new(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0);
+
+#[test]
+fn wide_window_has_bars_on_the_sides() {
+    let mut app = editor_in_window(WindowResolution::new(1080.0, 540.0));
+    assert_eq!(
+        viewport(&mut app),
+        (UVec2::new(405, 0), UVec2::new(270, 540))
+    );
+
+    let camera = camera_position(&mut app);
+    assert_cursor_at(&mut app, Vec2::new(540.0, 270.0), camera);
+    assert_cursor_at(&mut app, Vec2::new(405.0, 0.0), camera - HALF_SCREEN);
+    assert_cursor_at(&mut app, Vec2::new(675.0, 540.0), camera + HALF_SCREEN);
+}
+
+#[test]
+fn tall_window_has_bars_above_and_below() {
+    let mut app = editor_in_window(WindowResolution::new(270.0, 1080.0));
+    assert_eq!(
+        viewport(&mut app),
+        (UVec2::new(0, 270), UVec2::new(270, 540))
+    );
+
+    let camera = camera_position(&mut app);
+    assert_cursor_at(&mut app, Vec2::new(135.0, 540.0), camera);
+    assert_cursor_at(&mut app, Vec2::new(0.0, 270.0), camera - HALF_SCREEN);
+    assert_cursor_at(&mut app, Vec2::new(270.0, 810.0), camera + HALF_SCREEN);
+}
+
+#[test]
+fn viewport_is_in_physical_pixels_and_the_cursor_in_logical_ones() {
+    // 1080 physical pixels square, 540 logical ones
+    let mut app =
+        editor_in_window(WindowResolution::new(1080.0, 1080.0).with_scale_factor_override(2.0));
+    assert_eq!(
+        viewport(&mut app),
+        (UVec2::new(270, 0), UVec2::new(540, 1080))
+    );
+
+    let camera = camera_position(&mut app);
+    assert_cursor_at(&mut app, Vec2::new(270.0, 270.0), camera);
+    assert_cursor_at(&mut app, Vec2::new(135.0, 0.0), camera - HALF_SCREEN);
+    assert_cursor_at(&mut app, Vec2::new(405.0, 540.0), camera + HALF_SCREEN);
+}
@@ -0,0 +1,240 @@
+use bevy::prelude::*;
+use shoe_shmup::{PlayerRail, RailBranch, RailDirection, RailError, RailPosition};
+
+// 100 px long segments along the x axis: (0, 0) -> (100, 0) -> (200, 0)
+fn straight_rail() -> PlayerRail {
+    PlayerRail {
+        rail: vec![Vec2::ZERO, Vec2::new(100.0, 0.0), Vec2::new(200.0, 0.0)],
+        closed: false,
+        branches: vec![],
+        branch_nodes: vec![],
+    }
+}
+
+// 100 px square, counter clockwise from the origin
+fn square_rail() -> PlayerRail {
+    PlayerRail {
+        rail: vec![
+            Vec2::ZERO,
+            Vec2::new(100.0, 0.0),
+            Vec2::new(100.0, 100.0),
+            Vec2::new(0.0, 100.0),
+        ],
+        closed: true,
+        branches: vec![],
+        branch_nodes: vec![],
+    }
+}
+
+fn position(index: usize, position: f32, direction: RailDirection) -> RailPosition {
+    RailPosition {
+        index,
+        position,
+        direction,
+    }
+}
+
+fn assert_near(actual: Vec2, expected: Vec2) {
+    assert!(
+        actual.abs_diff_eq(expected, 1e-3),
+        "expected {expected}, got {actual}"
+    );
+}
+
+#[test]
+fn moves_within_segment() {
+    let rail = straight_rail();
+    let mut pos = position(0, 0.0, RailDirection::Positive);
+
+    let (point, at_node) = pos.next_position(&rail, 0.5, 100.0, None);
+
+    assert_near(point, Vec2::new(50.0, 0.0));
+    assert!(!at_node);
+    assert_eq!(pos.index, 0);
+}
+
+#[test]
+fn crosses_node_onto_next_segment() {
+    let rail = straight_rail();
+    let mut pos = position(0, 0.9, RailDirection::Positive);
+
+    let (point, at_node) = pos.next_position(&rail, 1.0, 30.0, None);
+
+    assert_near(point, Vec2::new(120.0, 0.0));
+    assert!(at_node);
+    assert_eq!(pos.index, 1);
+    assert_eq!(pos.direction, RailDirection::Positive);
+}
+
+#[test]
+fn crosses_node_backwards() {
+    let rail = straight_rail();
+    let mut pos = position(1, 0.1, RailDirection::Negative);
+
+    let (point, at_node) = pos.next_position(&rail, 1.0, 30.0, None);
+
+    assert_near(point, Vec2::new(80.0, 0.0));
+    assert!(at_node);
+    assert_eq!(pos.index, 0);
+    assert_eq!(pos.direction, RailDirection::Negative);
+}
+
+#[test]
+fn bounces_at_open_end_with_leftover_distance() {
+    let rail = straight_rail();
+    let mut pos = position(1, 0.9, RailDirection::Positive);
+
+    let (point, at_node) = pos.next_position(&rail, 1.0, 30.0, None);
+
+    assert_near(point, Vec2::new(180.0, 0.0));
+    assert!(at_node);
+    assert_eq!(pos.index, 1);
+    assert_eq!(pos.direction, RailDirection::Negative);
+}
+
+#[test]
+fn bounces_at_open_start() {
+    let rail = straight_rail();
+    let mut pos = position(0, 0.1, RailDirection::Negative);
+
+    let (point, _) = pos.next_position(&rail, 1.0, 30.0, None);
+
+    assert_near(point, Vec2::new(20.0, 0.0));
+    assert_eq!(pos.direction, RailDirection::Positive);
+}
+
+#[test]
+fn crosses_several_nodes_in_one_frame() {
+    let rail = straight_rail();
+    let mut pos = position(0, 0.5, RailDirection::Positive);
+
+    // 50 to the middle node, 100 to the end, then 30 back
+    let (point, at_node) = pos.next_position(&rail, 1.0, 180.0, None);
+
+    assert_near(point, Vec2::new(170.0, 0.0));
+    assert!(at_node);
+    assert_eq!(pos.index, 1);
+    assert_eq!(pos.direction, RailDirection::Negative);
+}
+
+#[test]
+fn wraps_around_closed_rail() {
+    let rail = square_rail();
+    // on the closing segment from (0, 100) back to the origin
+    let mut pos = position(3, 0.9, RailDirection::Positive);
+
+    let (point, at_node) = pos.next_position(&rail, 1.0, 30.0, None);
+
+    assert_near(point, Vec2::new(20.0, 0.0));
+    assert!(at_node);
+    assert_eq!(pos.index, 0);
+    assert_eq!(pos.direction, RailDirection::Positive);
+}
+
+#[test]
+fn wraps_around_closed_rail_backwards() {
+    let rail = square_rail();
+    let mut pos = position(0, 0.1, RailDirection::Negative);
+
+    let (point, _) = pos.next_position(&rail, 1.0, 30.0, None);
+
+    assert_near(point, Vec2::new(0.0, 20.0));
+    assert_eq!(pos.index, 3);
+    assert_eq!(pos.direction, RailDirection::Negative);
+}
+
+#[test]
+fn open_rail_does_not_wrap() {
+    let mut rail = square_rail();
+    rail.closed = false;
+    let mut pos = position(2, 0.9, RailDirection::Positive);
+
+    let (point, _) = pos.next_position(&rail, 1.0, 30.0, None);
+
+    assert_near(point, Vec2::new(20.0, 100.0));
+    assert_eq!(pos.index, 2);
+    assert_eq!(pos.direction, RailDirection::Negative);
+}
+
+// straight rail with a spur from the middle node up to (100, 100)
+fn junction_rail() -> PlayerRail {
+    let mut rail = straight_rail();
+    rail.branch_nodes.push(Vec2::new(100.0, 100.0));
+    rail.branches.push(RailBranch { from: 1, to: 3 });
+    rail
+}
+
+#[test]
+fn junction_continues_straight_without_steering() {
+    let rail = junction_rail();
+    let mut pos = position(0, 0.9, RailDirection::Positive);
+
+    let (point, _) = pos.next_position(&rail, 1.0, 30.0, None);
+
+    assert_near(point, Vec2::new(120.0, 0.0));
+    assert_eq!(pos.index, 1);
+}
+
+#[test]
+fn junction_follows_steering() {
+    let rail = junction_rail();
+    let mut pos = position(0, 0.9, RailDirection::Positive);
+
+    let (point, _) = pos.next_position(&rail, 1.0, 30.0, Some(Vec2::Y));
+
+    assert_near(point, Vec2::new(100.0, 20.0));
+    assert_eq!(pos.index, rail.segment_count() - 1);
+    assert_eq!(pos.direction, RailDirection::Positive);
+}
+
+#[test]
+fn branch_is_traversed_backwards_onto_rail() {
+    let rail = junction_rail();
+    let branch = rail.segment_count() - 1;
+    let mut pos = position(branch, 0.1, RailDirection::Negative);
+
+    let (point, _) = pos.next_position(&rail, 1.0, 30.0, Some(Vec2::NEG_X));
+
+    assert_near(point, Vec2::new(80.0, 0.0));
+    assert_eq!(pos.index, 0);
+    assert_eq!(pos.direction, RailDirection::Negative);
+}
+
+#[test]
+fn spur_turns_around_at_its_end() {
+    let rail = junction_rail();
+    let branch = rail.segment_count() - 1;
+    let mut pos = position(branch, 0.9, RailDirection::Positive);
+
+    let (point, at_node) = pos.next_position(&rail, 1.0, 30.0, Some(Vec2::Y));
+
+    assert_near(point, Vec2::new(100.0, 80.0));
+    assert!(at_node);
+    assert_eq!(pos.index, branch);
+    assert_eq!(pos.direction, RailDirection::Negative);
+}
+
+#[test]
+fn rails_that_can_be_moved_along_are_valid() {
+    assert_eq!(straight_rail().validate(), Ok(()));
+    assert_eq!(square_rail().validate(), Ok(()));
+    assert_eq!(junction_rail().validate(), Ok(()));
+}
+
+#[test]
+fn rails_without_a_segment_are_invalid() {
+    let mut rail = straight_rail();
+    rail.rail.truncate(1);
+    assert_eq!(rail.validate(), Err(RailError::TooFewNodes(1)));
+
+    rail.rail.clear();
+    assert_eq!(rail.validate(), Err(RailError::TooFewNodes(0)));
+}
+
+#[test]
+fn branches_to_missing_nodes_are_invalid() {
+    let mut rail = junction_rail();
+    rail.branches.push(RailBranch { from: 2, to: 4 });
+
+    assert_eq!(rail.validate(), Err(RailError::BranchOutOfRange(1)));
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use std::path::Path;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use common::{click_button, current_state, headless_app, run_until_state, temp_file};
+use shoe_shmup::{
+    Actions, ActionsSet, CurrentLevel, Enemy, GameState, Player, Replay, ReplayMode, ReplayResult,
+};
+
+// a 20 fps simulation gets through level 2 in a few hundred frames
+const FRAME_TIME: Duration = Duration::from_millis(50);
+const MAX_FRAMES: usize = 2000;
+
+// Scripted input, written where input devices are read so it reaches the simulation
+// at the same point of the frame as real input and playback.
+fn script_actions(
+    mut frames: Local<usize>,
+    mut actions: ResMut<Actions>,
+    player: Query<&Transform, With<Player>>,
+    enemies: Query<&Transform, With<Enemy>>,
+) {
+    // aims at the closest enemy
+    let target = player.get_single().ok().and_then(|player| {
+        let player = player.translation.truncate();
+        enemies
+            .iter()
+            .map(|t| t.translation.truncate())
+            .min_by(|a, b| a.distance(player).total_cmp(&b.distance(player)))
+    });
+    let frame = *frames;
+    *frames += 1;
+    actions.player_movement = Some(if frame % 120 < 60 { Vec2::X } else { Vec2::Y });
+    actions.player_point = target.or(actions.player_point);
+    actions.player_stop = frame % 40 < 10;
+    actions.player_shoot = frame % 40 == 5;
+}
+
+// plays level 2 with scripted input until the player dies or the level ends
+fn record_level2(path: &Path) {
+    let mut app = headless_app();
+    app.insert_resource(ReplayMode::record(path, Some(FRAME_TIME)));
+    app.add_system(script_actions.in_set(ActionsSet));
+    app.world.resource_mut::<CurrentLevel>().0 = 1;
+    click_button(&mut app);
+    run_until_state(&mut app, GameState::Playing);
+
+    for _ in 0..MAX_FRAMES {
+        if current_state(&app) != GameState::Playing {
+            return;
+        }
+        app.update();
+    }
+    panic!("level didn't end");
+}
+
+fn play_back(replay: Replay, verify: bool) -> App {
+    let mut app = headless_app();
+    app.insert_resource(ReplayMode::playback(replay, verify));
+    for _ in 0..MAX_FRAMES {
+        if !app.world.contains_resource::<ReplayMode>() {
+            return app;
+        }
+        app.update();
+    }
+    panic!("replay didn't end");
+}
+
+#[test]
+fn records_frames_until_level_ends() {
+    let path = temp_file("replay.ron");
+    record_level2(&path);
+
+    let replay = Replay::load(&path).unwrap();
+    assert_eq!(replay.level, 1);
+    assert!(replay.end.is_some());
+    assert!(replay.frames.len() > 1);
+    assert!(replay.frames.iter().all(|frame| frame.delta == FRAME_TIME));
+    assert!(replay.frames.iter().any(|frame| frame.player_shoot));
+    assert!(replay.end.unwrap().score > 0);
+}
+
+#[test]
+fn playback_reaches_recorded_end_state() {
+    let path = temp_file("replay.ron");
+    record_level2(&path);
+
+    let mut app = play_back(Replay::load(&path).unwrap(), true);
+    app.update();
+    assert!(app.world.resource::<ReplayResult>().matches());
+    run_until_state(&mut app, GameState::Menu);
+}
+
+#[test]
+fn playback_detects_divergence() {
+    let path = temp_file("replay.ron");
+    record_level2(&path);
+
+    let mut replay = Replay::load(&path).unwrap();
+    for frame in replay.frames.iter_mut() {
+        frame.player_stop = false;
+        frame.player_shoot = false;
+    }
+    let mut app = play_back(replay, false);
+    app.update();
+
+    let result = app.world.resource::<ReplayResult>();
+    assert!(!result.matches());
+    assert_eq!(result.actual.score, 0);
+}
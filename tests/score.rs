@@ -0,0 +1,109 @@
+mod common;
+
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use common::{
+    headless_app, headless_app_with_high_scores, run_until_state, set_state, start_level,
+    temp_high_score_file,
+};
+use shoe_shmup::{Bullet, Enemy, GameState, HighScores, Score, ScoreValue, SpawnBullet};
+
+// enemies in the level with their score values, lowest first
+fn enemies(app: &mut App) -> Vec<(Transform, u32)> {
+    let mut enemies: Vec<_> = app
+        .world
+        .query_filtered::<(&Transform, &ScoreValue), With<Enemy>>()
+        .iter(&app.world)
+        .map(|(t, v)| (*t, v.0))
+        .collect();
+    enemies.sort_by(|(a, _), (b, _)| a.translation.y.total_cmp(&b.translation.y));
+    enemies
+}
+
+fn shoot(app: &mut App, transform: Transform) {
+    app.world.send_event(SpawnBullet::player(transform));
+    app.update();
+    app.update();
+}
+
+#[test]
+fn kills_score_with_combo() {
+    let mut app = headless_app();
+    start_level(&mut app);
+    let enemies = enemies(&mut app);
+
+    shoot(&mut app, enemies[0].0);
+    assert_eq!(app.world.resource::<Score>().points, enemies[0].1);
+    assert_eq!(app.world.resource::<Score>().combo, 1);
+
+    shoot(&mut app, enemies[1].0);
+    let score = app.world.resource::<Score>();
+    assert_eq!(score.points, enemies[0].1 + 2 * enemies[1].1);
+    assert_eq!(score.combo, 2);
+}
+
+#[test]
+fn missing_breaks_combo() {
+    let mut app = headless_app();
+    start_level(&mut app);
+    let enemies = enemies(&mut app);
+    shoot(&mut app, enemies[0].0);
+    assert_eq!(app.world.resource::<Score>().combo, 1);
+
+    // sideways below the enemies, until the bullet leaves the screen
+    shoot(
+        &mut app,
+        Transform::from_xyz(0.0, -100.0, 0.0).with_rotation(Quat::from_rotation_z(-FRAC_PI_2)),
+    );
+    for _ in 0..30 {
+        app.update();
+    }
+    assert!(app
+        .world
+        .query::<&Bullet>()
+        .iter(&app.world)
+        .next()
+        .is_none());
+    assert_eq!(app.world.resource::<Score>().combo, 0);
+}
+
+#[test]
+fn high_score_table_keeps_best_scores() {
+    let mut high_scores = HighScores::default();
+    for points in 1..=12 {
+        assert!(high_scores.insert(points * 100));
+    }
+    assert_eq!(high_scores.scores.len(), 10);
+    assert_eq!(high_scores.scores[0], 1200);
+    assert_eq!(high_scores.scores[9], 300);
+    assert!(!high_scores.insert(100));
+    assert!(high_scores.insert(650));
+    assert_eq!(high_scores.scores[6], 650);
+}
+
+#[test]
+fn high_score_is_saved_when_run_ends() {
+    let file = temp_high_score_file();
+    let mut app = headless_app_with_high_scores(file.clone());
+    start_level(&mut app);
+    let enemies = enemies(&mut app);
+    shoot(&mut app, enemies[0].0);
+
+    set_state(&mut app, GameState::PlayerDead);
+    run_until_state(&mut app, GameState::Menu);
+    app.update();
+
+    assert_eq!(app.world.resource::<Score>().points, 0);
+    assert_eq!(
+        app.world.resource::<HighScores>().scores,
+        vec![enemies[0].1]
+    );
+    assert!(file.exists());
+
+    let app = headless_app_with_high_scores(file);
+    assert_eq!(
+        app.world.resource::<HighScores>().scores,
+        vec![enemies[0].1]
+    );
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use bevy::prelude::*;
+use common::{camera_y, headless_app, start_level2};
+use shoe_shmup::{
+    Enemy, GameState, Player, ScrollKeyframe, ScrollPause, ScrollTimeline, SCREEN_HEIGHT,
+};
+
+// level 2 scrolls at 30 from the start, slowing down to 10 at y = 150, where it stops for
+// 4 seconds, then speeds up to 40 at y = 450
+fn start_level2_without_player(app: &mut App) {
+    start_level2(app);
+
+    // enemy fire would end the level before the pauses do
+    let player = app
+        .world
+        .query_filtered::<Entity, With<Player>>()
+        .single(&app.world);
+    app.world.despawn(player);
+}
+
+fn set_camera_y(app: &mut App, y: f32) {
+    app.world
+        .query_filtered::<&mut Transform, With<Camera>>()
+        .single_mut(&mut app.world)
+        .translation
+        .y = y;
+}
+
+fn ticks(app: &App, seconds: f32) -> usize {
+    (seconds / app.world.resource::<FixedTime>().period.as_secs_f32()).round() as usize
+}
+
+#[test]
+fn speed_changes_linearly_between_keyframes() {
+    let keyframe = |y, speed| ScrollKeyframe {
+        y,
+        speed,
+        pause: None,
+    };
+    let timeline = ScrollTimeline {
+        keyframes: vec![keyframe(100.0, 10.0), keyframe(200.0, 30.0)],
+    };
+    assert_eq!(timeline.speed_at(0.0), 10.0);
+    assert_eq!(timeline.speed_at(150.0), 20.0);
+    assert_eq!(timeline.speed_at(500.0), 30.0);
+}
+
+#[test]
+fn camera_follows_timeline_and_pauses() {
+    let mut app = headless_app();
+    start_level2_without_player(&mut app);
+    let start = camera_y(&mut app);
+    app.update();
+    let moved = camera_y(&mut app) - start;
+    assert!(moved > 0.0 && moved <= 30.0 / ticks(&app, 1.0) as f32 + 1e-4);
+
+    set_camera_y(&mut app, 149.9);
+    app.update();
+    app.update();
+    assert_eq!(camera_y(&mut app), 150.0);
+    for _ in 0..ticks(&app, 3.5) {
+        app.update();
+    }
+    assert_eq!(camera_y(&mut app), 150.0);
+    for _ in 0..ticks(&app, 1.0) {
+        app.update();
+    }
+    assert!(camera_y(&mut app) > 150.0);
+}
+
+#[test]
+fn boss_pause_waits_for_enemies_on_screen() {
+    let mut app = headless_app();
+    start_level2_without_player(&mut app);
+    app.world
+        .query::<&mut ScrollTimeline>()
+        .single_mut(&mut app.world)
+        .keyframes[1]
+        .pause = Some(ScrollPause::UntilCleared);
+
+    set_camera_y(&mut app, 149.9);
+    for _ in 0..ticks(&app, 5.0) {
+        app.update();
+    }
+    assert_eq!(camera_y(&mut app), 150.0);
+
+    // enemies above the screen, or already passed below it, don't hold the camera
+    let screen_top = 150.0 + SCREEN_HEIGHT / 2.0;
+    let screen_bottom = 150.0 - SCREEN_HEIGHT / 2.0;
+    let on_screen: Vec<Entity> = app
+        .world
+        .query_filtered::<(Entity, &Transform), With<Enemy>>()
+        .iter(&app.world)
+        .filter(|(_, transform)| transform.translation.y <= screen_top)
+        .map(|(entity, _)| entity)
+        .collect();
+    assert!(on_screen.len() > 1);
+    app.world
+        .get_mut::<Transform>(on_screen[0])
+        .unwrap()
+        .translation
+        .y = screen_bottom - 50.0;
+    for &enemy in &on_screen[1..] {
+        app.world.despawn(enemy);
+    }
+    app.update();
+    app.update();
+    assert!(camera_y(&mut app) > 150.0);
+    assert_eq!(
+        app.world.resource::<State<GameState>>().0,
+        GameState::Playing
+    );
+}
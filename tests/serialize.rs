@@ -0,0 +1,81 @@
+mod common;
+
+use bevy::prelude::*;
+use common::{headless_app, start_level, temp_file, wait_for_save};
+use shoe_shmup::{migrate_level, Enemy, SaveSceneEvent};
+
+const OLD_LEVEL: &str = r#"(
+  entities: {
+    3: (
+      components: {
+        "shoe_shmup::enemy::Enemy": (),
+        "bevy_transform::components::transform::Transform": (
+          translation: (x: 10.0, y: 20.0, z: 1.0),
+          rotation: (0.0, 0.0, 0.0, 1.0),
+          scale: (x: 1.0, y: 1.0, z: 1.0),
+        ),
+        "bevy_render::view::visibility::ComputedVisibility": (
+          flags: (bits: 0),
+        ),
+        "bevy_render::view::visibility::Visibility": Inherited,
+      },
+    ),
+  },
+)"#;
+
+#[test]
+fn saving_writes_level_to_path() {
+    let mut app = headless_app();
+    start_level(&mut app);
+    let path = temp_file("levels").join("saved.scn.ron");
+    // like the shapes' mesh and material handles, which are only valid while the game runs
+    app.register_type::<Handle<DynamicScene>>();
+    let enemy = app
+        .world
+        .query_filtered::<Entity, With<Enemy>>()
+        .iter(&app.world)
+        .next()
+        .unwrap();
+    app.world
+        .entity_mut(enemy)
+        .insert(Handle::<DynamicScene>::default());
+
+    app.world.send_event(SaveSceneEvent { path: path.clone() });
+
+    assert_eq!(wait_for_save(&mut app).unwrap(), path);
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(contents.contains("shoe_shmup::enemy::Enemy"));
+    assert!(contents.contains("shoe_shmup::player_rail::PlayerRail"));
+    assert!(!contents.contains("GlobalTransform"));
+    assert!(!contents.contains("Visibility"));
+    assert!(!contents.contains("Handle"));
+}
+
+#[test]
+fn migration_keeps_only_authored_components() {
+    let app = headless_app();
+
+    let migrated = migrate_level(app.world.resource::<AppTypeRegistry>(), OLD_LEVEL).unwrap();
+
+    assert!(migrated.contains("shoe_shmup::enemy::Enemy"));
+    assert!(migrated.contains("y: 20.0"));
+    assert!(!migrated.contains("Visibility"));
+    // already migrated files stay the same
+    let again = migrate_level(app.world.resource::<AppTypeRegistry>(), &migrated).unwrap();
+    assert_eq!(again, migrated);
+}
+
+#[test]
+fn saving_to_bad_path_fails() {
+    let mut app = headless_app();
+    start_level(&mut app);
+    // a file where the parent directory should be
+    let not_a_dir = temp_file("not_a_dir");
+    std::fs::write(&not_a_dir, "").unwrap();
+
+    app.world.send_event(SaveSceneEvent {
+        path: not_a_dir.join("saved.scn.ron"),
+    });
+
+    assert!(wait_for_save(&mut app).is_err());
+}
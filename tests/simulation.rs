@@ -0,0 +1,129 @@
+mod common;
+
+use std::time::Duration;
+
+use bevy::ecs::event::ManualEventReader;
+use bevy::prelude::*;
+use common::{headless_app, start_level, FrameTime};
+use shoe_shmup::{DespawnWhenOffscreen, LeftScreen, Lifetime, Player, SCREEN_HEIGHT};
+
+const TICK: Duration = Duration::from_millis(20);
+
+fn start_with_frame_time(frame_time: Duration) -> App {
+    let mut app = headless_app();
+    app.insert_resource(FixedTime::new(TICK))
+        .insert_resource(FrameTime(frame_time));
+    start_level(&mut app);
+    app
+}
+
+fn camera(app: &mut App) -> (Transform, GlobalTransform) {
+    let (transform, global_transform) = app
+        .world
+        .query_filtered::<(&Transform, &GlobalTransform), With<Camera>>()
+        .single(&app.world);
+    (*transform, *global_transform)
+}
+
+fn player(app: &mut App) -> Vec3 {
+    app.world
+        .query_filtered::<&Transform, With<Player>>()
+        .single(&app.world)
+        .translation
+}
+
+// moves the same distance over 60 ticks whether they take 120 frames or 20
+#[test]
+fn movement_is_frame_rate_independent() {
+    let mut fast = start_with_frame_time(TICK / 2);
+    let mut slow = start_with_frame_time(TICK * 3);
+    let fast_start = (camera(&mut fast).0.translation, player(&mut fast));
+    let slow_start = (camera(&mut slow).0.translation, player(&mut slow));
+
+    for _ in 0..120 {
+        fast.update();
+    }
+    for _ in 0..20 {
+        slow.update();
+    }
+
+    let fast_camera = camera(&mut fast).0.translation - fast_start.0;
+    let slow_camera = camera(&mut slow).0.translation - slow_start.0;
+    assert!(fast_camera.abs_diff_eq(slow_camera, 1e-3));
+    assert!(fast_camera.y > 0.0);
+    let fast_player = player(&mut fast) - fast_start.1;
+    let slow_player = player(&mut slow) - slow_start.1;
+    assert!(fast_player.abs_diff_eq(slow_player, 1e-3));
+}
+
+#[test]
+fn rendering_interpolates_between_ticks() {
+    let mut app = start_with_frame_time(TICK / 4);
+    // interpolation starts after the first couple of ticks
+    for _ in 0..8 {
+        app.update();
+    }
+
+    let mut simulated = Vec::new();
+    let mut rendered = Vec::new();
+    for _ in 0..8 {
+        app.update();
+        let (transform, global_transform) = camera(&mut app);
+        simulated.push(transform.translation.y);
+        rendered.push(global_transform.translation().y);
+    }
+
+    simulated.dedup();
+    assert!(simulated.len() <= 3);
+    assert!(rendered.windows(2).all(|w| w[1] > w[0]));
+}
+
+#[test]
+fn entities_despawn_after_leaving_the_screen() {
+    let mut app = start_with_frame_time(TICK);
+    let camera = camera(&mut app).0.translation;
+    let offscreen = DespawnWhenOffscreen { margin: 10.0 };
+    let on_screen = app
+        .world
+        .spawn((Transform::from_translation(camera), offscreen))
+        .id();
+    // like an enemy further up the level
+    let above = camera + Vec3::Y * SCREEN_HEIGHT;
+    let above = app
+        .world
+        .spawn((Transform::from_translation(above), offscreen))
+        .id();
+    app.update();
+
+    let mut left_screen = ManualEventReader::<LeftScreen>::default();
+    let move_below = |app: &mut App, distance: f32| {
+        app.world
+            .get_mut::<Transform>(on_screen)
+            .unwrap()
+            .translation = camera - Vec3::Y * (SCREEN_HEIGHT / 2.0 + distance);
+        app.update();
+    };
+    move_below(&mut app, 5.0);
+    assert!(app.world.get_entity(on_screen).is_some());
+    move_below(&mut app, 15.0);
+    assert!(app.world.get_entity(on_screen).is_none());
+    assert!(app.world.get_entity(above).is_some());
+    let events = app.world.resource::<Events<LeftScreen>>();
+    let sent: Vec<LeftScreen> = left_screen.iter(events).copied().collect();
+    assert_eq!(sent, vec![LeftScreen(on_screen)]);
+}
+
+#[test]
+fn lifetime_runs_out_with_the_simulation() {
+    // five ticks, which take ten frames
+    let mut app = start_with_frame_time(TICK / 2);
+    let entity = app.world.spawn(Lifetime::from_seconds(0.1)).id();
+    for _ in 0..6 {
+        app.update();
+    }
+    assert!(app.world.get_entity(entity).is_some());
+    for _ in 0..8 {
+        app.update();
+    }
+    assert!(app.world.get_entity(entity).is_none());
+}
@@ -0,0 +1,167 @@
+mod common;
+
+use bevy::prelude::*;
+use common::{camera_y, current_state, headless_app, start_level};
+use shoe_shmup::{Enemy, EnemyKind, GameState, Player, SpawnCondition, SpawnTrigger, WaveEnemy};
+
+fn start(app: &mut App) {
+    start_level(app);
+    // enemy fire would end the level while waiting for waves
+    let player = app
+        .world
+        .query_filtered::<Entity, With<Player>>()
+        .single(&app.world);
+    app.world.despawn(player);
+}
+
+fn wave(positions: &[Vec2]) -> Vec<WaveEnemy> {
+    positions
+        .iter()
+        .map(|&position| WaveEnemy {
+            kind: "grunt".to_string(),
+            position,
+        })
+        .collect()
+}
+
+// the enemies placed in the level files don't have an `EnemyKind`
+fn spawned_enemies(app: &mut App) -> Vec<(Entity, Vec2)> {
+    app.world
+        .query_filtered::<(Entity, &Transform), (With<Enemy>, With<EnemyKind>)>()
+        .iter(&app.world)
+        .map(|(entity, transform)| (entity, transform.translation.truncate()))
+        .collect()
+}
+
+fn despawn_all(app: &mut App, entities: impl IntoIterator<Item = Entity>) {
+    for entity in entities {
+        app.world.despawn(entity);
+    }
+}
+
+#[test]
+fn wave_spawns_relative_to_camera_when_it_scrolls_past() {
+    let mut app = headless_app();
+    start(&mut app);
+    let threshold = camera_y(&mut app) + 1.0;
+    let trigger = app
+        .world
+        .spawn(SpawnTrigger {
+            order: 0,
+            condition: SpawnCondition::CameraY(threshold),
+            wave: wave(&[Vec2::new(-50.0, 300.0), Vec2::new(50.0, 300.0)]),
+        })
+        .id();
+
+    app.update();
+    assert!(spawned_enemies(&mut app).is_empty());
+
+    while camera_y(&mut app) < threshold {
+        app.update();
+    }
+    app.update();
+    let y = camera_y(&mut app);
+    let spawned: Vec<Vec2> = spawned_enemies(&mut app).iter().map(|(_, p)| *p).collect();
+    assert_eq!(spawned.len(), 2);
+    assert!(app.world.get_entity(trigger).is_none());
+    // the camera scrolled on for a tick since the wave spawned
+    for x in [-50.0, 50.0] {
+        assert!(spawned
+            .iter()
+            .any(|p| p.x == x && (p.y - (y + 300.0)).abs() < 1.0));
+    }
+}
+
+#[test]
+fn waves_follow_each_other() {
+    let mut app = headless_app();
+    start(&mut app);
+    app.world.spawn(SpawnTrigger {
+        order: 0,
+        condition: SpawnCondition::Delay(0.5),
+        wave: wave(&[Vec2::new(0.0, 200.0)]),
+    });
+
+    let period = app.world.resource::<FixedTime>().period.as_secs_f32();
+    for _ in 0..(0.4 / period) as usize {
+        app.update();
+    }
+    assert!(spawned_enemies(&mut app).is_empty());
+    for _ in 0..(0.1 / period) as usize + 2 {
+        app.update();
+    }
+    let first: Vec<Entity> = spawned_enemies(&mut app).iter().map(|(e, _)| *e).collect();
+    assert_eq!(first.len(), 1);
+
+    app.world.spawn(SpawnTrigger {
+        order: 1,
+        condition: SpawnCondition::PreviousWaveCleared,
+        wave: wave(&[Vec2::new(-40.0, 200.0), Vec2::new(40.0, 200.0)]),
+    });
+    for _ in 0..10 {
+        app.update();
+    }
+    assert_eq!(spawned_enemies(&mut app).len(), 1);
+
+    despawn_all(&mut app, first);
+    app.update();
+    app.update();
+    assert_eq!(spawned_enemies(&mut app).len(), 2);
+}
+
+// entity ids are reused, so a trigger loaded later can have a lower id than one loaded before it
+#[test]
+fn waves_follow_their_order_not_their_entities() {
+    let mut app = headless_app();
+    start(&mut app);
+    let mut entities = [app.world.spawn_empty().id(), app.world.spawn_empty().id()];
+    entities.sort();
+    app.world.entity_mut(entities[1]).insert(SpawnTrigger {
+        order: 0,
+        condition: SpawnCondition::PreviousWaveCleared,
+        wave: wave(&[Vec2::new(0.0, 200.0)]),
+    });
+    app.world.entity_mut(entities[0]).insert(SpawnTrigger {
+        order: 1,
+        condition: SpawnCondition::PreviousWaveCleared,
+        wave: wave(&[Vec2::new(-40.0, 200.0), Vec2::new(40.0, 200.0)]),
+    });
+
+    app.update();
+    let spawned: Vec<Entity> = spawned_enemies(&mut app).iter().map(|(e, _)| *e).collect();
+    assert_eq!(spawned.len(), 1);
+
+    despawn_all(&mut app, spawned);
+    app.update();
+    app.update();
+    assert_eq!(spawned_enemies(&mut app).len(), 2);
+}
+
+#[test]
+fn level_waits_for_pending_waves() {
+    let mut app = headless_app();
+    start(&mut app);
+    let trigger = app
+        .world
+        .spawn(SpawnTrigger {
+            order: 0,
+            condition: SpawnCondition::CameraY(10000.0),
+            wave: wave(&[Vec2::ZERO]),
+        })
+        .id();
+    let enemies: Vec<Entity> = app
+        .world
+        .query_filtered::<Entity, With<Enemy>>()
+        .iter(&app.world)
+        .collect();
+    despawn_all(&mut app, enemies);
+
+    app.update();
+    app.update();
+    assert_eq!(current_state(&app), GameState::Playing);
+
+    app.world.despawn(trigger);
+    app.update();
+    app.update();
+    assert_eq!(current_state(&app), GameState::LevelFinished);
+}